@@ -0,0 +1,586 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tiny_http::{Header, Method, Request, Response, Server};
+use crate::config::{write_atomic_private, ConfigState};
+use crate::downloader::{DownloadContext, DownloadFailure, DownloadObserver, DownloadRequest, Downloader, ProcessSlot};
+use crate::history::now_secs;
+use crate::paths::AppPaths;
+use crate::presets::PresetOverrides;
+use crate::storage::Storage;
+
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+const SSE_KEEPALIVE: Duration = Duration::from_secs(15);
+/// Finished jobs kept for `GET /jobs`, oldest are dropped first
+const MAX_FINISHED_JOBS: usize = 100;
+
+/// The API's download, separate from the UI queue's `CURRENT_PROCESS`
+static API_PROCESS: ProcessSlot = ProcessSlot::new();
+
+/// Contents of `app_config_dir/api.json`, where scripts find the server
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApiInfo {
+    /// Port on 127.0.0.1, None while the server is off
+    pub port: Option<u16>,
+    /// Sent as `Authorization: Bearer <token>`, or `?token=` for EventSource
+    pub token: String,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// A download enqueued over the API
+#[derive(Serialize, Clone, Debug)]
+pub struct ApiJob {
+    pub id: u64,
+    pub url: String,
+    pub preset: Option<String>,
+    pub download_dir: Option<String>,
+    pub status: JobStatus,
+    pub progress: u8,
+    /// Final paths of the downloaded files
+    pub files: Vec<String>,
+    pub error: Option<String>,
+    /// Unix timestamps in seconds
+    pub created_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    #[serde(skip)]
+    overrides: PresetOverrides,
+}
+
+/// Body of `POST /jobs`
+#[derive(Deserialize)]
+struct EnqueueRequest {
+    url: String,
+    preset: Option<String>,
+    download_dir: Option<String>,
+    #[serde(default)]
+    overrides: PresetOverrides,
+}
+
+/// What a request asks for, from its method and path
+#[derive(Debug, PartialEq, Eq)]
+enum Route {
+    Events,
+    ListJobs,
+    AddJob,
+    Job(u64),
+    CancelJob(u64),
+    NotFound,
+}
+
+impl Route {
+    fn parse(method: &Method, url: &str) -> Self {
+        let path = url.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (method, segments.as_slice()) {
+            (Method::Get, ["events"]) => Route::Events,
+            (Method::Get, ["jobs"]) => Route::ListJobs,
+            (Method::Post, ["jobs"]) => Route::AddJob,
+            (Method::Get, ["jobs", id]) => id.parse().map_or(Route::NotFound, Route::Job),
+            (Method::Post, ["jobs", id, "cancel"]) => id.parse().map_or(Route::NotFound, Route::CancelJob),
+            _ => Route::NotFound,
+        }
+    }
+}
+
+impl JobStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+/// Jobs run one at a time, separately from the UI queue
+#[derive(Default)]
+struct Jobs {
+    jobs: Mutex<Vec<ApiJob>>,
+    next_id: Mutex<u64>,
+    wake: Condvar,
+    /// Open `/events` streams
+    subscribers: Mutex<Vec<mpsc::Sender<String>>>,
+}
+
+impl Jobs {
+    fn get(&self, id: u64) -> Option<ApiJob> {
+        self.jobs.lock().unwrap().iter().find(|job| job.id == id).cloned()
+    }
+
+    fn update(&self, id: u64, change: impl FnOnce(&mut ApiJob)) {
+        let job = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let Some(job) = jobs.iter_mut().find(|job| job.id == id) else {
+                return;
+            };
+            change(job);
+            job.clone()
+        };
+        self.broadcast(&job);
+    }
+
+    fn broadcast(&self, job: &ApiJob) {
+        let Ok(json) = serde_json::to_string(job) else {
+            return;
+        };
+        let event = format!("event: job\ndata: {}\n\n", json);
+        // Closed streams drop their receiver
+        self.subscribers.lock().unwrap().retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+
+    fn add(&self, request: EnqueueRequest) -> ApiJob {
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        let job = {
+            let mut jobs = self.jobs.lock().unwrap();
+            Self::prune(&mut jobs);
+            let job = ApiJob {
+                id,
+                url: request.url,
+                preset: request.preset,
+                download_dir: request.download_dir,
+                status: JobStatus::Queued,
+                progress: 0,
+                files: Vec::new(),
+                error: None,
+                created_at: now_secs(),
+                started_at: None,
+                finished_at: None,
+                overrides: request.overrides,
+            };
+            jobs.push(job.clone());
+            job
+        };
+        self.wake.notify_all();
+        self.broadcast(&job);
+        job
+    }
+
+    // Keep the list from growing for as long as the app runs
+    fn prune(jobs: &mut Vec<ApiJob>) {
+        let finished = jobs.iter().filter(|job| job.status.is_finished()).count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+        jobs.retain(|job| {
+            if excess > 0 && job.status.is_finished() {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    fn cancel(&self, id: u64) -> Result<ApiJob, (u16, String)> {
+        let status = self.get(id).ok_or((404, format!("No job with id {}", id)))?.status;
+        match status {
+            JobStatus::Queued => self.update(id, |job| {
+                job.status = JobStatus::Cancelled;
+                job.finished_at = Some(now_secs());
+            }),
+            // The worker sees the status once yt-dlp exits
+            JobStatus::Running => {
+                self.update(id, |job| job.status = JobStatus::Cancelled);
+                let _ = API_PROCESS.terminate();
+            }
+            _ => return Err((409, "Job has already finished".to_string())),
+        }
+        Ok(self.get(id).unwrap())
+    }
+
+    /// Block until a job is queued and mark it running
+    fn next(&self) -> ApiJob {
+        let mut jobs = self.jobs.lock().unwrap();
+        loop {
+            if let Some(job) = jobs.iter_mut().find(|job| job.status == JobStatus::Queued) {
+                job.status = JobStatus::Running;
+                job.started_at = Some(now_secs());
+                let job = job.clone();
+                drop(jobs);
+                self.broadcast(&job);
+                return job;
+            }
+            jobs = self.wake.wait(jobs).unwrap();
+        }
+    }
+}
+
+/// Reports yt-dlp progress to `/events`
+#[derive(Clone)]
+struct JobObserver {
+    jobs: Arc<Jobs>,
+    id: u64,
+}
+
+impl DownloadObserver for JobObserver {
+    // yt-dlp's own message says more than the exit code
+    fn log(&self, line: &str) {
+        if line.starts_with("ERROR:") {
+            self.jobs.update(self.id, |job| job.error = Some(line.to_string()));
+        }
+    }
+
+    fn progress(&self, percent: u8) {
+        self.jobs.update(self.id, |job| job.progress = percent);
+    }
+
+    fn cancelled(&self) -> bool {
+        self.jobs.get(self.id).is_some_and(|job| job.status == JobStatus::Cancelled)
+    }
+}
+
+/// Optional HTTP API on 127.0.0.1 for scripts and browser extensions, enabled by
+/// `UserConfig::api_enabled`
+#[derive(Default)]
+pub struct LocalApi {
+    jobs: Arc<Jobs>,
+    server: Mutex<Option<Arc<Server>>>,
+    worker: OnceLock<()>,
+}
+
+impl LocalApi {
+    fn info_path(paths: &AppPaths) -> PathBuf {
+        paths.config_dir.join("api.json")
+    }
+
+    /// Keep the token across restarts so clients only need the new port
+    fn write_info(paths: &AppPaths, port: Option<u16>) -> Result<ApiInfo, String> {
+        let path = Self::info_path(paths);
+        let token = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<ApiInfo>(&content).ok())
+            .map(|info| info.token)
+            .filter(|token| token.len() >= 32)
+            .map_or_else(Self::new_token, Ok)?;
+
+        let info = ApiInfo { port, token };
+        fs::create_dir_all(&paths.config_dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+        let json = serde_json::to_string_pretty(&info).map_err(|e| format!("Failed to serialize API info: {}", e))?;
+        write_atomic_private(&path, json.as_bytes())?;
+        Ok(info)
+    }
+
+    fn new_token() -> Result<String, String> {
+        let mut bytes = [0u8; 32];
+        getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate API token: {}", e))?;
+        Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    /// Start or stop the server to match the config
+    pub fn apply(&self, app_handle: &tauri::AppHandle, enabled: bool) {
+        let result = if enabled { self.start(app_handle) } else { self.stop(app_handle) };
+        if let Err(e) = result {
+            eprintln!("Warning: {}", e);
+            let _ = app_handle.emit("config-warning", e);
+        }
+    }
+
+    fn start(&self, app_handle: &tauri::AppHandle) -> Result<(), String> {
+        let mut server = self.server.lock().unwrap();
+        if server.is_some() {
+            return Ok(());
+        }
+
+        let started = Arc::new(
+            Server::http("127.0.0.1:0").map_err(|e| format!("Failed to start local API: {}", e))?,
+        );
+        let port = started.server_addr().to_ip().map(|addr| addr.port());
+        let info = Self::write_info(&app_handle.state::<AppPaths>(), port)?;
+
+        self.worker.get_or_init(|| {
+            let jobs = self.jobs.clone();
+            let handle = app_handle.clone();
+            std::thread::spawn(move || Self::work(handle, jobs));
+        });
+
+        let listener = started.clone();
+        let jobs = self.jobs.clone();
+        let handle = app_handle.clone();
+        std::thread::spawn(move || {
+            for request in listener.incoming_requests() {
+                // Refused here so unauthenticated clients can't pile up threads
+                let Some(request) = Self::accept(request, &info.token) else {
+                    continue;
+                };
+                let jobs = jobs.clone();
+                let handle = handle.clone();
+                // Event streams stay open, so every accepted request gets its own thread
+                std::thread::spawn(move || Self::handle(&handle, &jobs, request));
+            }
+        });
+
+        *server = Some(started);
+        Ok(())
+    }
+
+    fn stop(&self, app_handle: &tauri::AppHandle) -> Result<(), String> {
+        let Some(server) = self.server.lock().unwrap().take() else {
+            return Ok(());
+        };
+        server.unblock();
+        // Ends open event streams, accepted jobs still finish
+        self.jobs.subscribers.lock().unwrap().clear();
+        Self::write_info(&app_handle.state::<AppPaths>(), None).map(|_| ())
+    }
+
+    fn work(app_handle: tauri::AppHandle, jobs: Arc<Jobs>) {
+        loop {
+            let job = jobs.next();
+            let observer = JobObserver { jobs: jobs.clone(), id: job.id };
+            let request = DownloadRequest {
+                url: job.url.clone(),
+                f_path: job.download_dir.clone(),
+                preset: job.preset.clone(),
+                overrides: job.overrides.clone(),
+            };
+            let result = DownloadContext::app(&app_handle)
+                .map_err(DownloadFailure::from)
+                .and_then(|context| {
+                    tauri::async_runtime::block_on(Downloader::download(&context, &observer, &API_PROCESS, request))
+                });
+            let disk_low = result.as_ref().err().and_then(|failure| failure.disk_low.clone());
+
+            jobs.update(job.id, |job| {
+                job.finished_at = Some(now_secs());
+                if job.status == JobStatus::Cancelled {
+                    return;
+                }
+                match result {
+                    Ok(result) => {
+                        job.status = JobStatus::Completed;
+                        job.progress = 100;
+                        job.files = result.files;
+                        // Left over from an attempt before a yt-dlp update
+                        job.error = None;
+                    }
+                    Err(e) => {
+                        job.status = JobStatus::Failed;
+                        job.error = Some(job.error.take().unwrap_or(e.message));
+                    }
+                }
+            });
+
+            // Queued jobs wait for space instead of failing one after another
+            if let Some(disk_low) = disk_low {
+                Storage::wait_for_space(Path::new(&disk_low.path), disk_low.threshold_bytes);
+            }
+        }
+    }
+
+    /// Answer CORS preflights and unauthorized requests, returning the others
+    fn accept(request: Request, token: &str) -> Option<Request> {
+        // Browser extensions can't carry the token in a preflight
+        if *request.method() == Method::Options {
+            let _ = request.respond(Self::with_cors(Response::empty(204)));
+            return None;
+        }
+
+        let query = request.url().split_once('?').map_or("", |(_, query)| query);
+        let authorization = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .map(|header| header.value.as_str());
+        if !Self::authorized(authorization, query, token) {
+            let _ = request.respond(Self::error(401, "Missing or invalid token"));
+            return None;
+        }
+        Some(request)
+    }
+
+    fn handle(app_handle: &tauri::AppHandle, jobs: &Jobs, mut request: Request) {
+        let response = match Route::parse(request.method(), request.url()) {
+            Route::Events => {
+                Self::stream_events(jobs, request);
+                return;
+            }
+            Route::ListJobs => Self::json(200, &*jobs.jobs.lock().unwrap()),
+            Route::AddJob => match Self::read_enqueue(app_handle, &mut request) {
+                Ok(enqueue) => Self::json(201, &jobs.add(enqueue)),
+                Err(e) => Self::error(400, &e),
+            },
+            Route::Job(id) => match jobs.get(id) {
+                Some(job) => Self::json(200, &job),
+                None => Self::error(404, "No such job"),
+            },
+            Route::CancelJob(id) => match jobs.cancel(id) {
+                Ok(job) => Self::json(200, &job),
+                Err((status, message)) => Self::error(status, &message),
+            },
+            Route::NotFound => Self::error(404, "Not found"),
+        };
+        let _ = request.respond(response);
+    }
+
+    /// Whether the `Authorization` header or the `token` query parameter carries the token
+    fn authorized(authorization: Option<&str>, query: &str, token: &str) -> bool {
+        let from_header = authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_string);
+        let from_query = url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "token")
+            .map(|(_, value)| value.into_owned());
+
+        from_header
+            .or(from_query)
+            .is_some_and(|given| Self::constant_time_eq(given.as_bytes(), token.as_bytes()))
+    }
+
+    fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+    }
+
+    fn read_enqueue(app_handle: &tauri::AppHandle, request: &mut Request) -> Result<EnqueueRequest, String> {
+        let mut body = String::new();
+        request
+            .as_reader()
+            .take(MAX_BODY_BYTES)
+            .read_to_string(&mut body)
+            .map_err(|e| format!("Failed to read request: {}", e))?;
+        let enqueue: EnqueueRequest = serde_json::from_str(&body).map_err(|e| format!("Invalid request: {}", e))?;
+
+        let url = url::Url::parse(&enqueue.url).map_err(|_| format!("Invalid URL: {}", enqueue.url))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Only http and https URLs can be downloaded: {}", enqueue.url));
+        }
+        if let Some(ref preset) = enqueue.preset {
+            if !app_handle.state::<ConfigState>().get().presets.contains_key(preset) {
+                return Err(format!("Unknown preset \"{}\"", preset));
+            }
+        }
+        Ok(enqueue)
+    }
+
+    /// Server-sent events: every job once, then each change as it happens
+    fn stream_events(jobs: &Jobs, request: Request) {
+        let (sender, receiver) = mpsc::channel();
+        let snapshot = jobs.jobs.lock().unwrap().clone();
+        jobs.subscribers.lock().unwrap().push(sender);
+
+        let mut writer = request.into_writer();
+        let head = "HTTP/1.1 200 OK\r\n\
+            Content-Type: text/event-stream\r\n\
+            Cache-Control: no-cache\r\n\
+            Access-Control-Allow-Origin: *\r\n\
+            Connection: close\r\n\r\n";
+        if writer.write_all(head.as_bytes()).is_err() {
+            return;
+        }
+        for job in snapshot {
+            if let Ok(json) = serde_json::to_string(&job) {
+                if writer.write_all(format!("event: job\ndata: {}\n\n", json).as_bytes()).is_err() {
+                    return;
+                }
+            }
+        }
+        let _ = writer.flush();
+
+        loop {
+            // Comments keep proxies from timing out and notice closed connections
+            let event = match receiver.recv_timeout(SSE_KEEPALIVE) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => ": keepalive\n\n".to_string(),
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+            if writer.write_all(event.as_bytes()).and_then(|_| writer.flush()).is_err() {
+                return;
+            }
+        }
+    }
+
+    fn with_cors<R: Read>(response: Response<R>) -> Response<R> {
+        response
+            .with_header(Header::from_bytes("Access-Control-Allow-Origin", "*").unwrap())
+            .with_header(Header::from_bytes("Access-Control-Allow-Headers", "Authorization, Content-Type").unwrap())
+            .with_header(Header::from_bytes("Access-Control-Allow-Methods", "GET, POST, OPTIONS").unwrap())
+    }
+
+    fn json<T: Serialize + ?Sized>(status: u16, body: &T) -> Response<std::io::Cursor<Vec<u8>>> {
+        let body = serde_json::to_string(body).unwrap_or_else(|_| "null".to_string());
+        Self::with_cors(Response::from_string(body))
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "application/json").unwrap())
+    }
+
+    fn error(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+        Self::json(status, &serde_json::json!({ "error": message }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "3f9a1c0d2b7e4a6f8c5d1e0b9a7c3f2e";
+
+    #[test]
+    fn requests_need_the_token() {
+        assert!(!LocalApi::authorized(None, "", TOKEN));
+        assert!(!LocalApi::authorized(Some("Bearer "), "token=", TOKEN));
+        assert!(!LocalApi::authorized(Some("Bearer wrong"), "", TOKEN));
+        assert!(!LocalApi::authorized(Some(TOKEN), "", TOKEN));
+        assert!(!LocalApi::authorized(None, &format!("token={}0", TOKEN), TOKEN));
+        assert!(!LocalApi::authorized(None, &format!("token={}", &TOKEN[1..]), TOKEN));
+
+        assert!(LocalApi::authorized(Some(&format!("Bearer {}", TOKEN)), "", TOKEN));
+        assert!(LocalApi::authorized(None, &format!("since=4&token={}", TOKEN), TOKEN));
+    }
+
+    #[test]
+    fn routes_by_method_and_path() {
+        assert_eq!(Route::parse(&Method::Get, "/events?token=x"), Route::Events);
+        assert_eq!(Route::parse(&Method::Get, "/jobs"), Route::ListJobs);
+        assert_eq!(Route::parse(&Method::Post, "/jobs/"), Route::AddJob);
+        assert_eq!(Route::parse(&Method::Get, "/jobs/7"), Route::Job(7));
+        assert_eq!(Route::parse(&Method::Post, "/jobs/7/cancel"), Route::CancelJob(7));
+
+        assert_eq!(Route::parse(&Method::Get, "/jobs/latest"), Route::NotFound);
+        assert_eq!(Route::parse(&Method::Delete, "/jobs/7"), Route::NotFound);
+        assert_eq!(Route::parse(&Method::Get, "/"), Route::NotFound);
+    }
+
+    fn enqueue(jobs: &Jobs) -> ApiJob {
+        jobs.add(EnqueueRequest {
+            url: "https://vimeo.com/76979871".to_string(),
+            preset: None,
+            download_dir: None,
+            overrides: PresetOverrides::default(),
+        })
+    }
+
+    #[test]
+    fn unknown_jobs_are_not_found() {
+        let jobs = Jobs::default();
+        let job = enqueue(&jobs);
+        assert!(jobs.get(job.id + 1).is_none());
+        assert_eq!(jobs.cancel(job.id + 1).unwrap_err().0, 404);
+    }
+
+    #[test]
+    fn keeps_the_newest_finished_jobs() {
+        let jobs = Jobs::default();
+        for _ in 0..MAX_FINISHED_JOBS + 5 {
+            let job = enqueue(&jobs);
+            jobs.update(job.id, |job| job.status = JobStatus::Completed);
+        }
+        let queued = enqueue(&jobs);
+
+        let kept = jobs.jobs.lock().unwrap().clone();
+        assert_eq!(kept.len(), MAX_FINISHED_JOBS + 1);
+        assert_eq!(kept[0].id, 6);
+        assert_eq!(kept.last().unwrap().id, queued.id);
+        assert_eq!(kept.last().unwrap().status, JobStatus::Queued);
+    }
+}
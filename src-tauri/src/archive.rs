@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use crate::config::write_atomic;
+use crate::paths::AppPaths;
+
+/// A line of a yt-dlp download archive, e.g. "youtube dQw4w9WgXcQ"
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    pub extractor: String,
+    pub video_id: String,
+}
+
+impl ArchiveEntry {
+    fn parse(line: &str) -> Option<Self> {
+        let (extractor, video_id) = line.trim().split_once(' ')?;
+        Some(Self {
+            extractor: extractor.to_string(),
+            video_id: video_id.trim().to_string(),
+        })
+    }
+
+    // yt-dlp stores the lowercased extractor key
+    fn same_video(&self, other: &ArchiveEntry) -> bool {
+        self.extractor.eq_ignore_ascii_case(&other.extractor) && self.video_id == other.video_id
+    }
+}
+
+/// Download archives yt-dlp maintains in `app_data_dir/archives`, one per preset
+/// (profile), so videos already fetched with a preset are skipped next time.
+pub struct DownloadArchive;
+
+impl DownloadArchive {
+    // Preset names are free text. Everything but lowercase letters, digits and
+    // '-' is escaped as `_xx` per UTF-8 byte, so distinct names never share a
+    // file, even on case-insensitive file systems.
+    fn file_name(preset: Option<&str>) -> String {
+        match preset.map(str::trim).filter(|name| !name.is_empty()) {
+            Some(name) => {
+                let mut escaped = String::new();
+                for byte in name.bytes() {
+                    if byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-' {
+                        escaped.push(byte as char);
+                    } else {
+                        escaped.push_str(&format!("_{:02x}", byte));
+                    }
+                }
+                format!("preset-{}.txt", escaped)
+            }
+            None => "default.txt".to_string(),
+        }
+    }
+
+    /// Archive file for a preset, the default profile when None
+    pub fn path(paths: &AppPaths, preset: Option<&str>) -> PathBuf {
+        paths.data_dir.join("archives").join(Self::file_name(preset))
+    }
+
+    // Before anything writes to an archive, including yt-dlp
+    fn create_dir(path: &Path) -> Result<(), String> {
+        match path.parent() {
+            Some(dir) => fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create archive directory: {}", e)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn ytdlp_args(paths: &AppPaths, preset: Option<&str>) -> Result<Vec<String>, String> {
+        let path = Self::path(paths, preset);
+        Self::create_dir(&path)?;
+        Ok(Self::ytdlp_args_for(&path))
+    }
+
+    pub fn ytdlp_args_for(path: &Path) -> Vec<String> {
+        vec!["--download-archive".to_string(), path.to_string_lossy().into_owned()]
+    }
+
+    /// Empty archive for a forced download: yt-dlp skips nothing and records what it
+    /// fetched, which `merge` then adds to the preset's archive
+    pub fn scratch_file() -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        std::env::temp_dir().join(format!("ezdl-archive-{}-{}.txt", std::process::id(), nanos))
+    }
+
+    /// Append entries of `scratch` missing from `archive`. The scratch file is removed.
+    pub fn merge(archive: &Path, scratch: &Path) -> Result<usize, String> {
+        let content = match fs::read_to_string(scratch) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(format!("Failed to read download archive: {}", e)),
+        };
+        let _ = fs::remove_file(scratch);
+
+        let existing: Vec<ArchiveEntry> = match fs::read_to_string(archive) {
+            Ok(content) => content.lines().filter_map(ArchiveEntry::parse).collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("Failed to read download archive: {}", e)),
+        };
+        let mut added: Vec<ArchiveEntry> = Vec::new();
+        for entry in content.lines().filter_map(ArchiveEntry::parse) {
+            if !existing.iter().chain(&added).any(|known| known.same_video(&entry)) {
+                added.push(entry);
+            }
+        }
+        if added.is_empty() {
+            return Ok(0);
+        }
+
+        Self::create_dir(archive)?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(archive)
+            .map_err(|e| format!("Failed to open download archive: {}", e))?;
+        for entry in &added {
+            writeln!(file, "{} {}", entry.extractor, entry.video_id)
+                .map_err(|e| format!("Failed to write download archive: {}", e))?;
+        }
+        Ok(added.len())
+    }
+
+    pub fn entries(paths: &AppPaths, preset: Option<&str>) -> Result<Vec<ArchiveEntry>, String> {
+        let path = Self::path(paths, preset);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Failed to read download archive: {}", e)),
+        };
+        Ok(content.lines().filter_map(ArchiveEntry::parse).collect())
+    }
+
+    /// Forget videos so the next download fetches them again. Returns how many were removed.
+    pub fn remove(
+        paths: &AppPaths,
+        preset: Option<&str>,
+        remove: &[ArchiveEntry],
+    ) -> Result<usize, String> {
+        let mut entries = Self::entries(paths, preset)?;
+        let before = entries.len();
+        entries.retain(|entry| !remove.iter().any(|r| r.same_video(entry)));
+
+        let removed = before - entries.len();
+        if removed > 0 {
+            let content: String = entries
+                .iter()
+                .map(|entry| format!("{} {}\n", entry.extractor, entry.video_id))
+                .collect();
+            write_atomic(&Self::path(paths, preset), content.as_bytes())
+                .map_err(|e| format!("Failed to write download archive: {}", e))?;
+        }
+        Ok(removed)
+    }
+
+    /// Keep a preset's archive when the preset is renamed. Refuses to replace an
+    /// archive already kept for the new name.
+    pub fn rename(paths: &AppPaths, old_name: &str, new_name: &str) -> Result<(), String> {
+        let old_path = Self::path(paths, Some(old_name));
+        if !old_path.exists() {
+            return Ok(());
+        }
+        let new_path = Self::path(paths, Some(new_name));
+        if new_path.exists() {
+            return Err(format!(
+                "A download archive for \"{}\" already exists in {}",
+                new_name,
+                new_path.display()
+            ));
+        }
+        fs::rename(&old_path, &new_path)
+            .map_err(|e| format!("Failed to rename download archive: {}", e))
+    }
+}
@@ -1,26 +1,175 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use tauri::Manager;
-use crate::config::ConfigManager;
+use crate::config::{BinarySources, ConfigState, UserConfig, YtDlpChannel};
+use crate::paths::AppPaths;
 
 pub struct BinaryManager;
 
+/// Where a binary asset is fetched from
+#[derive(Debug, Clone, PartialEq)]
+enum AssetLocation {
+    Remote(String),
+    Local(PathBuf),
+}
+
+impl std::fmt::Display for AssetLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetLocation::Remote(url) => write!(f, "{}", url),
+            AssetLocation::Local(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// How a binary was located
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BinarySource {
+    /// Explicit path from `UserConfig::binary_paths`
+    Override,
+    /// Downloaded into the app data directory
+    Bundled,
+    /// Found on PATH
+    SystemPath,
+    /// pipx or virtualenv install outside PATH
+    UserInstall,
+    /// Run through a Python interpreter with `-m`
+    PythonModule,
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct BinaryStatus {
     pub yt_dlp_installed: bool,
     pub ffmpeg_installed: bool,
+    pub ffprobe_installed: bool,
     pub yt_dlp_path: Option<String>,
     pub ffmpeg_path: Option<String>,
+    pub ffprobe_path: Option<String>,
+    pub yt_dlp_version: Option<String>,
+    pub ffmpeg_version: Option<String>,
+    pub ffprobe_version: Option<String>,
+    /// Arguments to put before yt-dlp's own (e.g. `-m yt_dlp` for Python module installs)
+    pub yt_dlp_args: Vec<String>,
+    pub yt_dlp_source: Option<BinarySource>,
+    pub ffmpeg_source: Option<BinarySource>,
+    pub ffprobe_source: Option<BinarySource>,
+    /// Why this yt-dlp was chosen, or why none was found
+    pub yt_dlp_reason: String,
+    pub ffmpeg_reason: String,
+    pub ffprobe_reason: String,
 }
 
-impl BinaryManager {
-    fn get_binaries_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
-        let data_dir = app_handle
-            .path()
-            .app_data_dir()
-            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+impl BinaryStatus {
+    /// Returns the subset of `tools` ("yt-dlp", "ffmpeg", "ffprobe") that is not installed
+    pub fn missing_tools(&self, tools: &[&'static str]) -> Vec<&'static str> {
+        tools
+            .iter()
+            .copied()
+            .filter(|tool| match *tool {
+                "yt-dlp" => !self.yt_dlp_installed,
+                "ffmpeg" => !self.ffmpeg_installed,
+                "ffprobe" => !self.ffprobe_installed,
+                _ => true,
+            })
+            .collect()
+    }
 
-        let binaries_dir = data_dir.join("binaries");
+    /// Whether yt-dlp will find ffprobe when pointed at ffmpeg's location,
+    /// which it only searches next to the ffmpeg binary
+    pub fn ffprobe_beside_ffmpeg(&self) -> bool {
+        match (&self.ffmpeg_path, &self.ffprobe_path) {
+            // Resolved only for the comparison, so /usr/bin and a symlinked /bin still match
+            (Some(ffmpeg), Some(ffprobe)) => {
+                let dir = |path: &str| {
+                    let parent = Path::new(path).parent()?;
+                    Some(fs::canonicalize(parent).unwrap_or_else(|_| parent.to_path_buf()))
+                };
+                dir(ffmpeg) == dir(ffprobe)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Last `BinaryManager::check_binaries` result, kept in managed state so downloads
+/// don't re-probe every binary. Cleared on config changes, binary installs and
+/// changes inside the binaries directory.
+#[derive(Default)]
+pub struct BinaryStatusCache {
+    status: Mutex<Option<BinaryStatus>>,
+    watcher: Mutex<Option<notify::RecommendedWatcher>>,
+}
+
+impl BinaryStatusCache {
+    // Get cached status, probing binaries if nothing is cached
+    pub fn get(&self, app_handle: &tauri::AppHandle) -> Result<BinaryStatus, String> {
+        let mut cached = self.status.lock().unwrap();
+        if let Some(ref status) = *cached {
+            return Ok(status.clone());
+        }
+
+        let config = app_handle.state::<ConfigState>().get();
+        let status = BinaryManager::check_binaries(&app_handle.state::<AppPaths>(), &config)?;
+        *cached = Some(status.clone());
+        Ok(status)
+    }
+
+    // Drop cached status and probe binaries again
+    pub fn refresh(&self, app_handle: &tauri::AppHandle) -> Result<BinaryStatus, String> {
+        self.invalidate();
+        self.get(app_handle)
+    }
+
+    pub fn invalidate(&self) {
+        *self.status.lock().unwrap() = None;
+    }
+
+    // Invalidate the cache whenever something in the binaries directory changes
+    pub fn watch(&self, app_handle: &tauri::AppHandle) -> Result<(), String> {
+        use notify::{RecursiveMode, Watcher};
+
+        let binaries_dir = BinaryManager::get_binaries_dir(&app_handle.state::<AppPaths>())?;
+        let handle = app_handle.clone();
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if event.is_ok_and(|e| !e.kind.is_access()) {
+                handle.state::<BinaryStatusCache>().invalidate();
+            }
+        })
+        .map_err(|e| format!("Failed to create binaries watcher: {}", e))?;
+
+        watcher
+            .watch(&binaries_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch binaries directory: {}", e))?;
+
+        *self.watcher.lock().unwrap() = Some(watcher);
+        Ok(())
+    }
+}
+
+/// Result of locating a single binary
+struct Resolution {
+    path: Option<String>,
+    args: Vec<String>,
+    source: Option<BinarySource>,
+    reason: String,
+}
+
+impl Resolution {
+    fn found(path: String, source: BinarySource, reason: String) -> Self {
+        Self { path: Some(path), args: Vec::new(), source: Some(source), reason }
+    }
+
+    fn missing(reason: String) -> Self {
+        Self { path: None, args: Vec::new(), source: None, reason }
+    }
+}
+
+impl BinaryManager {
+    fn get_binaries_dir(paths: &AppPaths) -> Result<PathBuf, String> {
+        let binaries_dir = paths.data_dir.join("binaries");
 
         if !binaries_dir.exists() {
             fs::create_dir_all(&binaries_dir)
@@ -40,20 +189,20 @@ impl BinaryManager {
     }
 
     // Get path to yt-dlp binary
-    pub fn get_ytdlp_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
-        let binaries_dir = Self::get_binaries_dir(app_handle)?;
+    pub fn get_ytdlp_path(paths: &AppPaths) -> Result<PathBuf, String> {
+        let binaries_dir = Self::get_binaries_dir(paths)?;
         Ok(binaries_dir.join(Self::get_binary_name("yt-dlp")))
     }
 
     // Get path to ffmpeg binary
-    pub fn get_ffmpeg_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
-        let binaries_dir = Self::get_binaries_dir(app_handle)?;
+    pub fn get_ffmpeg_path(paths: &AppPaths) -> Result<PathBuf, String> {
+        let binaries_dir = Self::get_binaries_dir(paths)?;
         Ok(binaries_dir.join(Self::get_binary_name("ffmpeg")))
     }
 
     // Get path to ffprobe binary
-    pub fn get_ffprobe_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
-        let binaries_dir = Self::get_binaries_dir(app_handle)?;
+    pub fn get_ffprobe_path(paths: &AppPaths) -> Result<PathBuf, String> {
+        let binaries_dir = Self::get_binaries_dir(paths)?;
         Ok(binaries_dir.join(Self::get_binary_name("ffprobe")))
     }
 
@@ -101,58 +250,163 @@ impl BinaryManager {
         Ok(())
     }
 
-    // Get download URL for yt-dlp based on current OS
-    fn get_ytdlp_download_url() -> Result<String, String> {
-        let url = if cfg!(target_os = "windows") {
-            "https://github.com/yt-dlp/yt-dlp/releases/latest/download/yt-dlp.exe"
-        } else if cfg!(target_os = "macos") {
-            "https://github.com/yt-dlp/yt-dlp/releases/latest/download/yt-dlp_macos"
-        } else if cfg!(target_os = "linux") {
-            "https://github.com/yt-dlp/yt-dlp/releases/latest/download/yt-dlp"
+    // Resolve an asset path against a mirror base (http(s) URL, file:// URL or local directory)
+    fn resolve_mirror(base: &str, asset_path: &str) -> Result<AssetLocation, String> {
+        let base = base.trim().trim_end_matches('/');
+        if base.starts_with("http://") || base.starts_with("https://") {
+            Ok(AssetLocation::Remote(format!("{}/{}", base, asset_path)))
+        } else if base.starts_with("file://") {
+            // Handles drive letters (file:///C:/mirror) and percent-encoding
+            let dir = url::Url::parse(base)
+                .ok()
+                .and_then(|url| url.to_file_path().ok())
+                .ok_or_else(|| format!("Invalid mirror location: {}", base))?;
+            Ok(AssetLocation::Local(dir.join(asset_path)))
         } else {
-            return Err("Unsupported operating system".to_string());
+            Ok(AssetLocation::Local(PathBuf::from(base).join(asset_path)))
+        }
+    }
+
+    // Get the pinned yt-dlp tag, rejecting values that can't be a release tag
+    fn get_ytdlp_version(sources: &BinarySources) -> Result<Option<&str>, String> {
+        match sources.ytdlp_version.as_deref().map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(tag) if tag.contains(['/', '\\']) || tag.contains("..") || tag.contains(char::is_whitespace) => {
+                Err(format!("Invalid yt-dlp version tag: {}", tag))
+            }
+            Some(tag) => Ok(Some(tag)),
+        }
+    }
+
+    // Error for platforms without a published build of the given binary
+    fn unsupported_platform(binary_name: &str) -> String {
+        format!(
+            "No prebuilt {} is available for {} ({}). Please install it from your package manager and enable system binaries in Settings.",
+            binary_name,
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        )
+    }
+
+    // Get release asset name of yt-dlp for current OS and architecture
+    fn get_ytdlp_asset_name() -> Result<&'static str, String> {
+        use std::env::consts::{ARCH, OS};
+
+        match (OS, ARCH) {
+            ("windows", "x86_64") => Ok("yt-dlp.exe"),
+            ("windows", "x86") => Ok("yt-dlp_x86.exe"),
+            ("windows", "aarch64") => Ok("yt-dlp_arm64.exe"),
+            // Universal binary covering Intel and Apple Silicon
+            ("macos", "x86_64" | "aarch64") => Ok("yt-dlp_macos"),
+            ("linux", "x86_64") => Ok("yt-dlp_linux"),
+            ("linux", "aarch64") => Ok("yt-dlp_linux_aarch64"),
+            ("linux", "arm") => Ok("yt-dlp_linux_armv7l"),
+            _ => Err(Self::unsupported_platform("yt-dlp")),
+        }
+    }
+
+    // Get download location for yt-dlp based on current OS and configured source
+    fn get_ytdlp_download_url(sources: &BinarySources) -> Result<AssetLocation, String> {
+        let asset = Self::get_ytdlp_asset_name()?;
+        let version = Self::get_ytdlp_version(sources)?;
+
+        if let Some(ref mirror) = sources.ytdlp_mirror {
+            if !mirror.trim().is_empty() {
+                let asset_path = match version {
+                    Some(tag) => format!("{}/{}", tag, asset),
+                    None => asset.to_string(),
+                };
+                return Self::resolve_mirror(mirror, &asset_path);
+            }
+        }
+
+        let repo = match sources.ytdlp_channel {
+            YtDlpChannel::Stable => "yt-dlp/yt-dlp",
+            YtDlpChannel::Nightly => "yt-dlp/yt-dlp-nightly-builds",
+            YtDlpChannel::Master => "yt-dlp/yt-dlp-master-builds",
+        };
+
+        let url = match version {
+            Some(tag) => format!("https://github.com/{}/releases/download/{}/{}", repo, tag, asset),
+            None => format!("https://github.com/{}/releases/latest/download/{}", repo, asset),
         };
 
-        Ok(url.to_string())
+        Ok(AssetLocation::Remote(url))
     }
 
-    // Get download URL for ffmpeg based on current OS
-    fn get_ffmpeg_download_url() -> Result<String, String> {
+    // Get download location for ffmpeg based on current OS, architecture and configured source.
+    // On macOS ffmpeg and ffprobe ship as separate archives, selected by binary_name.
+    fn get_ffmpeg_download_url(sources: &BinarySources, binary_name: &str) -> Result<AssetLocation, String> {
+        use std::env::consts::{ARCH, OS};
+
+        const BTBN: &str = "https://github.com/BtbN/FFmpeg-Builds/releases/download/latest";
+        const YTDLP_BUILDS: &str = "https://github.com/yt-dlp/FFmpeg-Builds/releases/download/latest";
+
         // Using builds from GitHub releases or official builds
-        let url = if cfg!(target_os = "windows") {
-            "https://github.com/BtbN/FFmpeg-Builds/releases/download/latest/ffmpeg-master-latest-win64-gpl.zip"
-        } else if cfg!(target_os = "macos") {
-            "https://evermeet.cx/ffmpeg/getrelease/ffmpeg/zip"
-        } else if cfg!(target_os = "linux") {
-            // Use GitHub release with direct binary download
-            "https://github.com/yt-dlp/FFmpeg-Builds/releases/download/latest/ffmpeg-master-latest-linux64-gpl.tar.xz"
-        } else {
-            return Err("Unsupported operating system".to_string());
+        let (asset, default_url) = match (OS, ARCH) {
+            ("windows", "x86_64") => {
+                let asset = "ffmpeg-master-latest-win64-gpl.zip".to_string();
+                let url = format!("{}/{}", BTBN, asset);
+                (asset, url)
+            }
+            ("windows", "aarch64") => {
+                let asset = "ffmpeg-master-latest-winarm64-gpl.zip".to_string();
+                let url = format!("{}/{}", BTBN, asset);
+                (asset, url)
+            }
+            // evermeet.cx only publishes Intel builds
+            ("macos", "x86_64") => (
+                format!("{}.zip", binary_name),
+                format!("https://evermeet.cx/ffmpeg/getrelease/{}/zip", binary_name),
+            ),
+            ("macos", "aarch64") => (
+                format!("{}-arm64.zip", binary_name),
+                format!("https://ffmpeg.martin-riedl.de/redirect/latest/macos/arm64/release/{}.zip", binary_name),
+            ),
+            ("linux", "x86_64") => {
+                let asset = "ffmpeg-master-latest-linux64-gpl.tar.xz".to_string();
+                let url = format!("{}/{}", YTDLP_BUILDS, asset);
+                (asset, url)
+            }
+            ("linux", "aarch64") => {
+                let asset = "ffmpeg-master-latest-linuxarm64-gpl.tar.xz".to_string();
+                let url = format!("{}/{}", YTDLP_BUILDS, asset);
+                (asset, url)
+            }
+            _ => return Err(Self::unsupported_platform("ffmpeg")),
         };
 
-        Ok(url.to_string())
+        match sources.ffmpeg_mirror {
+            Some(ref mirror) if !mirror.trim().is_empty() => Self::resolve_mirror(mirror, &asset),
+            _ => Ok(AssetLocation::Remote(default_url)),
+        }
     }
 
-    pub async fn download_ytdlp(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    // Fetch an asset into memory, emitting progress for remote downloads
+    async fn fetch_asset(
+        app_handle: &tauri::AppHandle,
+        client: &reqwest::Client,
+        location: &AssetLocation,
+        label: &str,
+    ) -> Result<Vec<u8>, String> {
         use tauri::Emitter;
         use futures_util::StreamExt;
 
-        let url = Self::get_ytdlp_download_url()?;
-        let dest_path = Self::get_ytdlp_path(app_handle)?;
-
-        let _ = app_handle.emit("binary-download-status", "Downloading yt-dlp...");
-        println!("Downloading yt-dlp from: {}", url);
-
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(120))
-            .build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        let url = match location {
+            AssetLocation::Local(path) => {
+                let bytes = fs::read(path)
+                    .map_err(|e| format!("Failed to read {} from {}: {}", label, path.display(), e))?;
+                let _ = app_handle.emit("binary-download-progress", (label, 100u8));
+                return Ok(bytes);
+            }
+            AssetLocation::Remote(url) => url,
+        };
 
         let response = client
-            .get(&url)
+            .get(url)
             .send()
             .await
-            .map_err(|e| format!("Failed to download yt-dlp: {}", e))?;
+            .map_err(|e| format!("Failed to download {}: {}", label, e))?;
 
         if !response.status().is_success() {
             return Err(format!("Download failed with status: {}", response.status()));
@@ -170,10 +424,30 @@ impl BinaryManager {
 
             if total_size > 0 {
                 let percent = ((downloaded as f64 / total_size as f64) * 100.0) as u8;
-                let _ = app_handle.emit("binary-download-progress", ("yt-dlp", percent));
+                let _ = app_handle.emit("binary-download-progress", (label, percent));
             }
         }
 
+        Ok(buffer)
+    }
+
+    pub async fn download_ytdlp(app_handle: &tauri::AppHandle) -> Result<(), String> {
+        use tauri::Emitter;
+
+        let config = app_handle.state::<ConfigState>().get();
+        let source = Self::get_ytdlp_download_url(&config.binary_sources)?;
+        let dest_path = Self::get_ytdlp_path(&app_handle.state::<AppPaths>())?;
+
+        let _ = app_handle.emit("binary-download-status", "Downloading yt-dlp...");
+        println!("Downloading yt-dlp from: {}", source);
+
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(120))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let buffer = Self::fetch_asset(app_handle, &client, &source, "yt-dlp").await?;
+
         fs::write(&dest_path, buffer)
             .map_err(|e| format!("Failed to write binary file: {}", e))?;
 
@@ -186,10 +460,12 @@ impl BinaryManager {
 
     pub async fn download_ffmpeg(app_handle: &tauri::AppHandle) -> Result<(), String> {
         use tauri::Emitter;
-        use futures_util::StreamExt;
 
-        let ffmpeg_path = Self::get_ffmpeg_path(app_handle)?;
-        let ffprobe_path = Self::get_ffprobe_path(app_handle)?;
+        let config = app_handle.state::<ConfigState>().get();
+        let sources = &config.binary_sources;
+        let paths = app_handle.state::<AppPaths>();
+        let ffmpeg_path = Self::get_ffmpeg_path(&paths)?;
+        let ffprobe_path = Self::get_ffprobe_path(&paths)?;
 
         let _ = app_handle.emit("binary-download-status", "Downloading ffmpeg...");
 
@@ -200,74 +476,27 @@ impl BinaryManager {
 
         // For macOS, download ffmpeg and ffprobe separately
         if cfg!(target_os = "macos") {
-            println!("Downloading ffmpeg...");
+            let source = Self::get_ffmpeg_download_url(sources, "ffmpeg")?;
+            println!("Downloading ffmpeg from: {}", source);
             let _ = app_handle.emit("binary-download-status", "Downloading ffmpeg binary...");
-            let ffmpeg_url = "https://evermeet.cx/ffmpeg/getrelease/ffmpeg/zip";
-
-            let response = client.get(ffmpeg_url).send().await
-                .map_err(|e| format!("Failed to download ffmpeg: {}", e))?;
-            if !response.status().is_success() {
-                return Err(format!("Download failed with status: {}", response.status()));
-            }
-
-            let total_size = response.content_length().unwrap_or(0);
-            let mut downloaded: u64 = 0;
-            let mut stream = response.bytes_stream();
-            let mut buffer = Vec::new();
-
-            while let Some(chunk) = stream.next().await {
-                let chunk = chunk.map_err(|e| format!("Failed to read chunk: {}", e))?;
-                buffer.extend_from_slice(&chunk);
-                downloaded += chunk.len() as u64;
-
-                if total_size > 0 {
-                    let percent = ((downloaded as f64 / total_size as f64) * 100.0) as u8;
-                    let _ = app_handle.emit("binary-download-progress", ("ffmpeg", percent));
-                }
-            }
 
+            let buffer = Self::fetch_asset(app_handle, &client, &source, "ffmpeg").await?;
             Self::extract_single_binary_macos(&buffer, &ffmpeg_path, "ffmpeg")?;
             Self::make_executable(&ffmpeg_path)?;
 
-            println!("Downloading ffprobe...");
+            let source = Self::get_ffmpeg_download_url(sources, "ffprobe")?;
+            println!("Downloading ffprobe from: {}", source);
             let _ = app_handle.emit("binary-download-status", "Downloading ffprobe binary...");
-            let ffprobe_url = "https://evermeet.cx/ffmpeg/getrelease/ffprobe/zip";
-            let response = client.get(ffprobe_url).send().await
-                .map_err(|e| format!("Failed to download ffprobe: {}", e))?;
-            if !response.status().is_success() {
-                return Err(format!("Download failed with status: {}", response.status()));
-            }
-            let bytes = response.bytes().await
-                .map_err(|e| format!("Failed to read ffprobe bytes: {}", e))?;
-            Self::extract_single_binary_macos(&bytes, &ffprobe_path, "ffprobe")?;
+
+            let buffer = Self::fetch_asset(app_handle, &client, &source, "ffprobe").await?;
+            Self::extract_single_binary_macos(&buffer, &ffprobe_path, "ffprobe")?;
             Self::make_executable(&ffprobe_path)?;
         } else {
             // For Windows/Linux, download single archive with both binaries
-            let url = Self::get_ffmpeg_download_url()?;
-            println!("Downloading ffmpeg from: {}", url);
+            let source = Self::get_ffmpeg_download_url(sources, "ffmpeg")?;
+            println!("Downloading ffmpeg from: {}", source);
 
-            let response = client.get(&url).send().await
-                .map_err(|e| format!("Failed to download ffmpeg: {}", e))?;
-
-            if !response.status().is_success() {
-                return Err(format!("Download failed with status: {}", response.status()));
-            }
-
-            let total_size = response.content_length().unwrap_or(0);
-            let mut downloaded: u64 = 0;
-            let mut stream = response.bytes_stream();
-            let mut buffer = Vec::new();
-
-            while let Some(chunk) = stream.next().await {
-                let chunk = chunk.map_err(|e| format!("Failed to read chunk: {}", e))?;
-                buffer.extend_from_slice(&chunk);
-                downloaded += chunk.len() as u64;
-
-                if total_size > 0 {
-                    let percent = ((downloaded as f64 / total_size as f64) * 100.0) as u8;
-                    let _ = app_handle.emit("binary-download-progress", ("ffmpeg", percent));
-                }
-            }
+            let buffer = Self::fetch_asset(app_handle, &client, &source, "ffmpeg").await?;
 
             if cfg!(target_os = "windows") {
                 Self::extract_ffmpeg_windows(&buffer, &ffmpeg_path, &ffprobe_path)?;
@@ -454,57 +683,315 @@ impl BinaryManager {
         Err("macOS extraction not supported on this platform".to_string())
     }
 
-    // Check if binary exists in system PATH
-    fn is_in_system_path(binary_name: &str) -> bool {
+    // Search PATH for a binary, returning its absolute location as found. Symlinks are
+    // kept: a Homebrew bin/ link outlives the versioned Cellar path it points to.
+    fn find_in_path(binary_name: &str) -> Option<PathBuf> {
+        let exe_name = Self::get_binary_name(binary_name);
+        let path_var = std::env::var_os("PATH")?;
+
+        std::env::split_paths(&path_var)
+            // Relative PATH entries depend on the working directory, skip them
+            .filter(|dir| dir.is_absolute())
+            .map(|dir| dir.join(&exe_name))
+            .find(|candidate| candidate.is_file() && Self::is_binary_valid(candidate))
+    }
+
+    // Locations pipx and virtualenvs install into, which GUI launches often lack on PATH
+    fn user_install_candidates(binary_name: &str) -> Vec<PathBuf> {
+        let exe_name = Self::get_binary_name(binary_name);
+        let bin_dir = if cfg!(windows) { "Scripts" } else { "bin" };
+        let mut dirs = Vec::new();
+
+        if let Some(venv) = std::env::var_os("VIRTUAL_ENV") {
+            dirs.push(PathBuf::from(venv).join(bin_dir));
+        }
+        if let Some(pipx_bin) = std::env::var_os("PIPX_BIN_DIR") {
+            dirs.push(PathBuf::from(pipx_bin));
+        }
+        if let Some(home) = dirs_next::home_dir() {
+            dirs.push(home.join(".local").join("bin"));
+            dirs.push(home.join(".local").join("pipx").join("venvs").join(binary_name).join(bin_dir));
+        }
+
+        dirs.into_iter().map(|dir| dir.join(&exe_name)).collect()
+    }
+
+    // Find a Python interpreter on PATH that can run the given module
+    fn find_python_module(module: &str) -> Option<PathBuf> {
         use std::process::Command;
 
-        Command::new(binary_name)
-            .arg("--version")
-            .output()
-            .is_ok()
+        ["python3", "python"].iter().find_map(|python| {
+            let interpreter = Self::find_in_path(python)?;
+            let runs = Command::new(&interpreter)
+                .args(["-m", module, "--version"])
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false);
+            runs.then_some(interpreter)
+        })
     }
 
-    // Check status of binaries (respects use_system_binaries config)
-    pub fn check_binaries(app_handle: &tauri::AppHandle) -> Result<BinaryStatus, String> {
-        let config = ConfigManager::load_config(app_handle)?;
-        let use_system = config.use_system_binaries;
+    fn path_to_string(path: &Path, binary_name: &str) -> Result<String, String> {
+        path.to_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("{} path contains invalid UTF-8", binary_name))
+    }
+
+    // Locate a binary: explicit override, then bundled, then system locations (if enabled)
+    fn resolve_binary(
+        paths: &AppPaths,
+        binary_name: &str,
+        override_path: Option<&str>,
+        use_system: bool,
+        python_module: Option<&str>,
+    ) -> Result<Resolution, String> {
+        if let Some(custom) = override_path.map(str::trim).filter(|p| !p.is_empty()) {
+            let path = PathBuf::from(custom);
+            if !path.is_absolute() {
+                return Ok(Resolution::missing(format!("Configured {} path is not absolute: {}", binary_name, custom)));
+            }
+            if !path.is_file() || !Self::is_binary_valid(&path) {
+                return Ok(Resolution::missing(format!("Configured {} path is not an executable file: {}", binary_name, custom)));
+            }
+            return Ok(Resolution::found(
+                Self::path_to_string(&path, binary_name)?,
+                BinarySource::Override,
+                "Using path configured in Settings".to_string(),
+            ));
+        }
 
-        let ytdlp_path = Self::get_ytdlp_path(app_handle)?;
-        let ffmpeg_path = Self::get_ffmpeg_path(app_handle)?;
+        let bundled = Self::get_binaries_dir(paths)?.join(Self::get_binary_name(binary_name));
+        if Self::is_binary_valid(&bundled) {
+            return Ok(Resolution::found(
+                Self::path_to_string(&bundled, binary_name)?,
+                BinarySource::Bundled,
+                "Using bundled binary from the app data directory".to_string(),
+            ));
+        }
 
-        // Check bundled binaries
-        let ytdlp_bundled = Self::is_binary_valid(&ytdlp_path);
-        let ffmpeg_bundled = Self::is_binary_valid(&ffmpeg_path);
+        if !use_system {
+            return Ok(Resolution::missing(format!(
+                "Bundled {} not downloaded and system binaries are disabled in Settings",
+                binary_name
+            )));
+        }
 
-        // Check system PATH (only if use_system_binaries is enabled)
-        let ytdlp_system = use_system && Self::is_in_system_path("yt-dlp");
-        let ffmpeg_system = use_system && Self::is_in_system_path("ffmpeg");
+        if let Some(path) = Self::find_in_path(binary_name) {
+            let reason = format!("Bundled {} not downloaded, found on PATH", binary_name);
+            return Ok(Resolution::found(Self::path_to_string(&path, binary_name)?, BinarySource::SystemPath, reason));
+        }
 
-        // Priority: bundled first, then system (if enabled)
-        let ytdlp_valid = ytdlp_bundled || ytdlp_system;
-        let ffmpeg_valid = ffmpeg_bundled || ffmpeg_system;
+        if let Some(path) = Self::user_install_candidates(binary_name)
+            .into_iter()
+            .find(|candidate| candidate.is_file() && Self::is_binary_valid(candidate))
+        {
+            let reason = format!("Not on PATH, found pipx/virtualenv install in {}", path.display());
+            return Ok(Resolution::found(Self::path_to_string(&path, binary_name)?, BinarySource::UserInstall, reason));
+        }
+
+        if let Some(module) = python_module {
+            if let Some(interpreter) = Self::find_python_module(module) {
+                let mut resolution = Resolution::found(
+                    Self::path_to_string(&interpreter, binary_name)?,
+                    BinarySource::PythonModule,
+                    format!("No {} executable found, running it as a Python module ({} -m {})", binary_name, interpreter.display(), module),
+                );
+                resolution.args = vec!["-m".to_string(), module.to_string()];
+                return Ok(resolution);
+            }
+        }
+
+        Ok(Resolution::missing(format!(
+            "{} not found: not bundled, not on PATH and no pipx/virtualenv install",
+            binary_name
+        )))
+    }
+
+    // Read the version a binary reports, None if it fails to run
+    fn probe_version(path: &str, args: &[String], version_flag: &str) -> Option<String> {
+        use std::process::Command;
+
+        let output = Command::new(path).args(args).arg(version_flag).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let first_line = stdout.lines().next()?;
+        let words: Vec<&str> = first_line.split_whitespace().collect();
+
+        // ffmpeg/ffprobe print "ffmpeg version 7.1 Copyright ...", yt-dlp just the version
+        match words.as_slice() {
+            [_, "version", version, ..] => Some(version.to_string()),
+            [version, ..] => Some(version.to_string()),
+            [] => None,
+        }
+    }
+
+    // Check status of binaries (respects use_system_binaries config)
+    pub fn check_binaries(paths: &AppPaths, config: &UserConfig) -> Result<BinaryStatus, String> {
+        let use_system = config.use_system_binaries;
+        let overrides = &config.binary_paths;
+
+        let ytdlp = Self::resolve_binary(paths, "yt-dlp", overrides.yt_dlp.as_deref(), use_system, Some("yt_dlp"))?;
+        let ffmpeg = Self::resolve_binary(paths, "ffmpeg", overrides.ffmpeg.as_deref(), use_system, None)?;
+        let ffprobe = Self::resolve_binary(paths, "ffprobe", overrides.ffprobe.as_deref(), use_system, None)?;
+
+        let yt_dlp_version = ytdlp.path.as_deref()
+            .and_then(|path| Self::probe_version(path, &ytdlp.args, "--version"));
+        let ffmpeg_version = ffmpeg.path.as_deref()
+            .and_then(|path| Self::probe_version(path, &ffmpeg.args, "-version"));
+        let ffprobe_version = ffprobe.path.as_deref()
+            .and_then(|path| Self::probe_version(path, &ffprobe.args, "-version"));
 
         Ok(BinaryStatus {
-            yt_dlp_installed: ytdlp_valid,
-            ffmpeg_installed: ffmpeg_valid,
-            yt_dlp_path: if ytdlp_bundled {
-                Some(ytdlp_path.to_str()
-                    .ok_or("yt-dlp path contains invalid UTF-8")?
-                    .to_string())
-            } else if ytdlp_system {
-                Some("yt-dlp".to_string())
-            } else {
-                None
-            },
-            ffmpeg_path: if ffmpeg_bundled {
-                Some(ffmpeg_path.to_str()
-                    .ok_or("ffmpeg path contains invalid UTF-8")?
-                    .to_string())
-            } else if ffmpeg_system {
-                Some("ffmpeg".to_string())
-            } else {
-                None
-            },
+            yt_dlp_installed: ytdlp.path.is_some(),
+            ffmpeg_installed: ffmpeg.path.is_some(),
+            ffprobe_installed: ffprobe.path.is_some(),
+            yt_dlp_path: ytdlp.path,
+            ffmpeg_path: ffmpeg.path,
+            ffprobe_path: ffprobe.path,
+            yt_dlp_version,
+            ffmpeg_version,
+            ffprobe_version,
+            yt_dlp_args: ytdlp.args,
+            yt_dlp_source: ytdlp.source,
+            ffmpeg_source: ffmpeg.source,
+            ffprobe_source: ffprobe.source,
+            yt_dlp_reason: ytdlp.reason,
+            ffmpeg_reason: ffmpeg.reason,
+            ffprobe_reason: ffprobe.reason,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(channel: YtDlpChannel, version: Option<&str>, mirror: Option<&str>) -> BinarySources {
+        BinarySources {
+            ytdlp_channel: channel,
+            ytdlp_version: version.map(str::to_string),
+            ytdlp_mirror: mirror.map(str::to_string),
+            ffmpeg_mirror: None,
+        }
+    }
+
+    fn asset() -> &'static str {
+        BinaryManager::get_ytdlp_asset_name().unwrap()
+    }
+
+    #[test]
+    fn mirror_http_base_is_joined() {
+        assert_eq!(
+            BinaryManager::resolve_mirror(" https://mirror.example.com/yt-dlp/ ", "2025.01.15/yt-dlp_linux").unwrap(),
+            AssetLocation::Remote("https://mirror.example.com/yt-dlp/2025.01.15/yt-dlp_linux".to_string())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn mirror_file_url_and_directory_are_local() {
+        assert_eq!(
+            BinaryManager::resolve_mirror("file:///srv/mirror/", "yt-dlp_linux").unwrap(),
+            AssetLocation::Local(PathBuf::from("/srv/mirror").join("yt-dlp_linux"))
+        );
+        assert_eq!(
+            BinaryManager::resolve_mirror("file:///srv/my%20mirror", "yt-dlp_linux").unwrap(),
+            AssetLocation::Local(PathBuf::from("/srv/my mirror").join("yt-dlp_linux"))
+        );
+        assert_eq!(
+            BinaryManager::resolve_mirror("/srv/mirror", "yt-dlp_linux").unwrap(),
+            AssetLocation::Local(PathBuf::from("/srv/mirror").join("yt-dlp_linux"))
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn mirror_file_url_keeps_drive_letter() {
+        assert_eq!(
+            BinaryManager::resolve_mirror("file:///C:/mirror/", "yt-dlp.exe").unwrap(),
+            AssetLocation::Local(PathBuf::from(r"C:\mirror").join("yt-dlp.exe"))
+        );
+        assert_eq!(
+            BinaryManager::resolve_mirror(r"C:\mirror", "yt-dlp.exe").unwrap(),
+            AssetLocation::Local(PathBuf::from(r"C:\mirror").join("yt-dlp.exe"))
+        );
+    }
+
+    // Remote hosts in file URLs are only meaningful on Windows (UNC shares)
+    #[cfg(unix)]
+    #[test]
+    fn mirror_file_url_with_host_is_rejected() {
+        assert!(BinaryManager::resolve_mirror("file://server/mirror", "yt-dlp_linux").is_err());
+    }
+
+    #[test]
+    fn stable_latest_by_default() {
+        let location = BinaryManager::get_ytdlp_download_url(&BinarySources::default()).unwrap();
+        assert_eq!(
+            location,
+            AssetLocation::Remote(format!("https://github.com/yt-dlp/yt-dlp/releases/latest/download/{}", asset()))
+        );
+    }
+
+    #[test]
+    fn channels_use_their_repositories() {
+        let nightly = BinaryManager::get_ytdlp_download_url(&sources(YtDlpChannel::Nightly, None, None)).unwrap();
+        assert_eq!(
+            nightly,
+            AssetLocation::Remote(format!(
+                "https://github.com/yt-dlp/yt-dlp-nightly-builds/releases/latest/download/{}",
+                asset()
+            ))
+        );
+
+        let master = BinaryManager::get_ytdlp_download_url(&sources(YtDlpChannel::Master, Some("2025.02.01.123456"), None)).unwrap();
+        assert_eq!(
+            master,
+            AssetLocation::Remote(format!(
+                "https://github.com/yt-dlp/yt-dlp-master-builds/releases/download/2025.02.01.123456/{}",
+                asset()
+            ))
+        );
+    }
+
+    #[test]
+    fn pinned_tag_uses_release_url() {
+        let location = BinaryManager::get_ytdlp_download_url(&sources(YtDlpChannel::Stable, Some(" 2025.01.15 "), None)).unwrap();
+        assert_eq!(
+            location,
+            AssetLocation::Remote(format!("https://github.com/yt-dlp/yt-dlp/releases/download/2025.01.15/{}", asset()))
+        );
+    }
+
+    #[test]
+    fn mirror_overrides_channel_and_nests_tag() {
+        let latest = BinaryManager::get_ytdlp_download_url(
+            &sources(YtDlpChannel::Nightly, None, Some("https://mirror.example.com")),
+        ).unwrap();
+        assert_eq!(latest, AssetLocation::Remote(format!("https://mirror.example.com/{}", asset())));
+
+        let pinned = BinaryManager::get_ytdlp_download_url(
+            &sources(YtDlpChannel::Stable, Some("2025.01.15"), Some("https://mirror.example.com/")),
+        ).unwrap();
+        assert_eq!(pinned, AssetLocation::Remote(format!("https://mirror.example.com/2025.01.15/{}", asset())));
+    }
+
+    #[test]
+    fn blank_mirror_and_version_are_ignored() {
+        let location = BinaryManager::get_ytdlp_download_url(&sources(YtDlpChannel::Stable, Some(""), Some("  "))).unwrap();
+        assert_eq!(
+            location,
+            AssetLocation::Remote(format!("https://github.com/yt-dlp/yt-dlp/releases/latest/download/{}", asset()))
+        );
+    }
+
+    #[test]
+    fn invalid_tags_are_rejected() {
+        for tag in ["../../evil", "2025/01", "2025 01"] {
+            assert!(BinaryManager::get_ytdlp_download_url(&sources(YtDlpChannel::Stable, Some(tag), None)).is_err());
+        }
+    }
+}
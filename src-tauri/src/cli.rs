@@ -0,0 +1,325 @@
+use crate::binary_manager::BinaryManager;
+use crate::config::{ConfigManager, UserConfig};
+use crate::downloader::{DownloadContext, DownloadFailure, DownloadObserver, DownloadRequest, Downloader, ProcessSlot};
+use crate::history::{HistoryQuery, HistoryStore};
+use crate::paths::AppPaths;
+use crate::presets::PresetOverrides;
+use crate::queue::{SavedQueue, SavedQueueItem};
+use crate::storage::DirValidation;
+
+/// Downloads run by `get` and `queue run`, one at a time
+static CLI_PROCESS: ProcessSlot = ProcessSlot::new();
+
+const COMMANDS: [&str; 6] = ["get", "queue", "history", "help", "--help", "-h"];
+
+const USAGE: &str = "\
+Usage:
+  ezdl                                  Open the app
+  ezdl URL... [--preset NAME] [--dir DIR]
+                                        Queue URLs in the app, opening it if needed
+  ezdl get URL... [options]             Download URLs with the app's settings
+      --preset NAME                     Use a saved preset
+      --dir DIR                         Download into DIR
+      --audio-only                      Extract audio
+      --playlist                        Download whole playlists
+      --force                           Ignore the download archive
+      --quiet                           Only print the downloaded files
+  ezdl queue list                       Show the remembered queue
+  ezdl queue run [--quiet]              Download the remembered queue
+  ezdl history [--search TEXT] [--site SITE] [--limit N]
+                                        Show downloaded videos
+";
+
+/// Whether the arguments ask for the command line instead of the window
+pub fn is_command(args: &[String]) -> bool {
+    args.get(1).is_some_and(|arg| COMMANDS.contains(&arg.as_str()))
+}
+
+/// Run a command and return the process exit code: yt-dlp's for downloads,
+/// 2 for usage errors
+pub fn run(args: &[String]) -> i32 {
+    attach_console();
+
+    let result = match args.first().map(String::as_str) {
+        Some("get") => get(&args[1..]),
+        Some("queue") => queue(&args[1..]),
+        Some("history") => history(&args[1..]),
+        _ => {
+            print!("{}", USAGE);
+            return 0;
+        }
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(CliError::Usage(message)) => {
+            eprintln!("ezdl: {}\n\n{}", message, USAGE);
+            2
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("ezdl: {}", message);
+            1
+        }
+    }
+}
+
+enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Failed(message)
+    }
+}
+
+// Release builds on Windows have no console of their own
+#[cfg(windows)]
+fn attach_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// Prints yt-dlp output to stderr, keeping stdout for the downloaded paths
+#[derive(Clone)]
+struct ConsoleObserver {
+    quiet: bool,
+}
+
+impl DownloadObserver for ConsoleObserver {
+    fn log(&self, line: &str) {
+        if !self.quiet {
+            eprintln!("{}", line);
+        }
+    }
+
+    // yt-dlp's own progress lines are already printed
+    fn progress(&self, _percent: u8) {}
+
+    fn dir_invalid(&self, validation: &DirValidation) {
+        eprintln!("Warning: {}, using the default download folder", validation.message);
+    }
+}
+
+/// Splits `--flag value` options from positional arguments
+struct Options {
+    positional: Vec<String>,
+    values: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: &[String], value_options: &[&str], flag_options: &[&str]) -> Result<Self, CliError> {
+        let mut options = Options { positional: Vec::new(), values: Vec::new(), flags: Vec::new() };
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if value_options.contains(&arg.as_str()) {
+                let value = args.next().ok_or_else(|| CliError::Usage(format!("{} needs a value", arg)))?;
+                options.values.push((arg.clone(), value.clone()));
+            } else if flag_options.contains(&arg.as_str()) {
+                options.flags.push(arg.clone());
+            } else if arg.starts_with("--") {
+                return Err(CliError::Usage(format!("Unknown option {}", arg)));
+            } else {
+                options.positional.push(arg.clone());
+            }
+        }
+        Ok(options)
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        self.values.iter().rev().find(|(option, _)| option == name).map(|(_, value)| value.clone())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+}
+
+/// Config, binaries and history shared with the app
+struct Context {
+    paths: AppPaths,
+    config: UserConfig,
+    history: HistoryStore,
+}
+
+impl Context {
+    fn load() -> Result<Self, String> {
+        let paths = AppPaths::headless()?;
+        let (config, warnings) = ConfigManager::load_config(&paths)?;
+        for warning in warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        let history = HistoryStore::new(&paths);
+        Ok(Self { paths, config, history })
+    }
+
+    /// Download one URL, printing the final paths
+    fn download(&self, request: DownloadRequest, observer: &ConsoleObserver) -> Result<(), DownloadFailure> {
+        let context = DownloadContext {
+            paths: &self.paths,
+            config: self.config.clone(),
+            status: BinaryManager::check_binaries(&self.paths, &self.config)?,
+            history: &self.history,
+            app_handle: None,
+            // Ctrl-C in the terminal has to reach yt-dlp
+            new_process_group: false,
+        };
+
+        let result = tauri::async_runtime::block_on(Downloader::download(&context, observer, &CLI_PROCESS, request))
+            .inspect_err(|failure| {
+                if failure.update_suggested {
+                    eprintln!("yt-dlp failed to extract this site. Updating yt-dlp from the app will likely fix it.");
+                }
+            })?;
+        for file in result.files {
+            println!("{}", file);
+        }
+        Ok(())
+    }
+}
+
+fn get(args: &[String]) -> Result<i32, CliError> {
+    let options = Options::parse(
+        args,
+        &["--preset", "--dir"],
+        &["--audio-only", "--playlist", "--force", "--quiet"],
+    )?;
+    if options.positional.is_empty() {
+        return Err(CliError::Usage("get needs at least one URL".to_string()));
+    }
+
+    let context = Context::load()?;
+    let observer = ConsoleObserver { quiet: options.flag("--quiet") };
+
+    // Flags only switch things on, the preset decides everything else
+    let overrides = PresetOverrides {
+        audio_only: options.flag("--audio-only").then_some(true),
+        playlist: options.flag("--playlist").then_some(true),
+        force_download: options.flag("--force").then_some(true),
+        ..Default::default()
+    };
+
+    // A failed URL doesn't stop the others, as in `queue run`
+    let mut exit_code = 0;
+    for url in &options.positional {
+        let request = DownloadRequest {
+            url: url.clone(),
+            f_path: options.value("--dir"),
+            preset: options.value("--preset"),
+            overrides: overrides.clone(),
+        };
+        if let Err(failure) = context.download(request, &observer) {
+            eprintln!("Download failed for {}: {}", url, failure.message);
+            exit_code = failure.code.unwrap_or(1);
+            if failure.disk_low.is_some() {
+                eprintln!("Stopping, free up some space and run the command again.");
+                break;
+            }
+        }
+    }
+    Ok(exit_code)
+}
+
+fn queue(args: &[String]) -> Result<i32, CliError> {
+    let options = Options::parse(args, &[], &["--quiet"])?;
+
+    match options.positional.first().map(String::as_str) {
+        Some("list") => {
+            let paths = AppPaths::headless()?;
+            for item in SavedQueue::load(&paths)? {
+                println!("{}", describe_queue_item(&item));
+            }
+            Ok(0)
+        }
+        Some("run") => {
+            let context = Context::load()?;
+            let observer = ConsoleObserver { quiet: options.flag("--quiet") };
+            let mut remaining = SavedQueue::load(&context.paths)?;
+            let mut exit_code = 0;
+
+            // Finished items leave the queue as they do in the app, failed ones stay
+            let mut index = 0;
+            while index < remaining.len() {
+                let item = remaining[index].clone();
+                let browser = item.preset.as_ref()
+                    .and_then(|name| context.config.presets.get(name))
+                    .and_then(|preset| preset.cookies_from_browser.clone());
+                let request = DownloadRequest {
+                    url: item.url.clone(),
+                    f_path: item.f_path.clone(),
+                    preset: item.preset.clone(),
+                    overrides: item.overrides(browser.as_deref()),
+                };
+
+                match context.download(request, &observer) {
+                    Ok(()) => {
+                        remaining.remove(index);
+                        SavedQueue::save(&context.paths, &remaining)?;
+                    }
+                    Err(failure) => {
+                        eprintln!("Download failed for {}: {}", item.url, failure.message);
+                        exit_code = failure.code.unwrap_or(1);
+                        if failure.disk_low.is_some() {
+                            eprintln!("Stopping, free up some space and run the queue again.");
+                            break;
+                        }
+                        index += 1;
+                    }
+                }
+            }
+            Ok(exit_code)
+        }
+        _ => Err(CliError::Usage("queue needs list or run".to_string())),
+    }
+}
+
+fn describe_queue_item(item: &SavedQueueItem) -> String {
+    let mut line = item.url.clone();
+    if let Some(title) = item.title.as_ref().filter(|title| **title != item.url) {
+        line.push_str(&format!("\t{}", title));
+    }
+    if let Some(ref preset) = item.preset {
+        line.push_str(&format!("\t[{}]", preset));
+    }
+    line
+}
+
+fn history(args: &[String]) -> Result<i32, CliError> {
+    let options = Options::parse(args, &["--search", "--site", "--limit"], &[])?;
+    if let Some(arg) = options.positional.first() {
+        return Err(CliError::Usage(format!("Unexpected argument {}", arg)));
+    }
+
+    let limit = match options.value("--limit") {
+        Some(limit) => Some(limit.parse().map_err(|_| CliError::Usage(format!("Invalid limit: {}", limit)))?),
+        None => None,
+    };
+    let query = HistoryQuery {
+        search: options.value("--search"),
+        site: options.value("--site"),
+        limit,
+        ..Default::default()
+    };
+
+    let paths = AppPaths::headless()?;
+    for entry in HistoryStore::new(&paths).query(&query)? {
+        let title = entry.title.as_deref().unwrap_or(&entry.url);
+        println!("{}\t{}", title, entry.webpage_url.as_deref().unwrap_or(&entry.url));
+        for file in &entry.files {
+            println!("\t{}", file);
+        }
+    }
+    Ok(0)
+}
@@ -0,0 +1,157 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use crate::config::{ClipboardMode, ConfigState, UserConfig};
+use crate::history::HistoryStore;
+use crate::queue::{DownloadQueue, QueuedDownload};
+use crate::site_rules::SiteRules;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Recently reported links, which aren't reported again when copied a second time
+const MAX_REPORTED: usize = 100;
+
+/// Sites yt-dlp downloads from that people commonly copy links of. Links to
+/// other sites are picked up when a site rule matches them, or with
+/// `ClipboardSettings::any_site`.
+const KNOWN_SITES: [&str; 22] = [
+    "youtube.com", "youtu.be", "music.youtube.com", "vimeo.com", "dailymotion.com",
+    "twitch.tv", "soundcloud.com", "bandcamp.com", "mixcloud.com", "tiktok.com",
+    "instagram.com", "facebook.com", "twitter.com", "x.com", "reddit.com",
+    "bilibili.com", "nicovideo.jp", "rumble.com", "odysee.com", "archive.org",
+    "bitchute.com", "streamable.com",
+];
+
+/// Payload of `clipboard-url-detected`
+#[derive(Serialize, Clone, Debug)]
+pub struct ClipboardUrl {
+    pub url: String,
+}
+
+/// Watches the clipboard for video links while `UserConfig::clipboard` asks for it
+pub struct ClipboardMonitor;
+
+impl ClipboardMonitor {
+    /// The copied text as a downloadable link, if it is one
+    fn video_url(text: &str, config: &UserConfig) -> Option<String> {
+        let text = text.trim();
+        if text.is_empty() || text.contains(char::is_whitespace) {
+            return None;
+        }
+
+        let url = url::Url::parse(text).ok()?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return None;
+        }
+        let host = url.host_str()?.trim_start_matches("www.").trim_start_matches("m.");
+
+        if config.clipboard.any_site {
+            return Some(text.to_string());
+        }
+        let known = KNOWN_SITES
+            .iter()
+            .any(|site| host == *site || host.ends_with(&format!(".{}", site)));
+        let ruled = matches!(SiteRules::find(&config.site_rules, text), Ok(Some(_)));
+        (known || ruled).then(|| text.to_string())
+    }
+
+    pub fn start(app_handle: tauri::AppHandle) {
+        std::thread::spawn(move || {
+            let mut clipboard = None;
+            let mut last_text: Option<String> = None;
+            let mut reported = VecDeque::new();
+            let mut was_enabled = false;
+            let mut warned = false;
+
+            loop {
+                std::thread::sleep(POLL_INTERVAL);
+
+                let config = app_handle.state::<ConfigState>().get();
+                if config.clipboard.mode == ClipboardMode::Off {
+                    was_enabled = false;
+                    continue;
+                }
+
+                // Created lazily, there may be no display when the app starts
+                if clipboard.is_none() {
+                    match arboard::Clipboard::new() {
+                        Ok(created) => clipboard = Some(created),
+                        Err(e) => {
+                            if !warned {
+                                eprintln!("Warning: Clipboard unavailable: {}", e);
+                                warned = true;
+                            }
+                            continue;
+                        }
+                    }
+                }
+                // Images and files read as no text
+                let text = clipboard.as_mut().and_then(|clipboard| clipboard.get_text().ok()).unwrap_or_default();
+
+                // Only copies made while watching count, not what was already there
+                let is_new = was_enabled && last_text.as_deref() != Some(text.as_str());
+                was_enabled = true;
+                last_text = Some(text.clone());
+                if !is_new {
+                    continue;
+                }
+
+                let Some(url) = Self::video_url(&text, &config) else {
+                    continue;
+                };
+                if reported.contains(&url) {
+                    continue;
+                }
+                reported.push_back(url.clone());
+                if reported.len() > MAX_REPORTED {
+                    reported.pop_front();
+                }
+                if app_handle.state::<HistoryStore>().contains_url(&url).unwrap_or(false) {
+                    continue;
+                }
+
+                match config.clipboard.mode {
+                    ClipboardMode::Notify => {
+                        let _ = app_handle.emit("clipboard-url-detected", ClipboardUrl { url });
+                    }
+                    ClipboardMode::Enqueue => {
+                        DownloadQueue::enqueue(&app_handle, vec![QueuedDownload {
+                            url,
+                            title: None,
+                            preset: config.clipboard.preset.clone(),
+                            download_dir: None,
+                            source: "clipboard".to_string(),
+                        }], false);
+                    }
+                    ClipboardMode::Off => {}
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_up_video_sites_unless_any_site_is_on() {
+        let mut config = UserConfig::default();
+        let url = |text: &str, config: &UserConfig| ClipboardMonitor::video_url(text, config);
+
+        assert_eq!(
+            url(" https://m.youtube.com/watch?v=dQw4w9WgXcQ\n", &config),
+            Some("https://m.youtube.com/watch?v=dQw4w9WgXcQ".to_string())
+        );
+        assert_eq!(url("https://videos.example.org/clip/1", &config), None);
+        assert_eq!(url("check https://vimeo.com/76979871", &config), None);
+
+        config.clipboard.any_site = true;
+        assert_eq!(
+            url("https://videos.example.org/clip/1", &config),
+            Some("https://videos.example.org/clip/1".to_string())
+        );
+        assert_eq!(url("ftp://videos.example.org/clip/1", &config), None);
+        assert_eq!(url("videos.example.org/clip/1", &config), None);
+    }
+}
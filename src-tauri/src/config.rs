@@ -1,93 +1,536 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use tauri::Manager;
+use crate::paths::AppPaths;
+use crate::presets::DownloadPreset;
+use crate::site_rules::SiteRule;
+use crate::storage::{DirValidation, Storage};
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+/// Current `UserConfig` schema version. Bump it together with a new entry in
+/// `MIGRATIONS` whenever stored values need rewriting; plain new fields only
+/// need a default.
+pub const CONFIG_VERSION: u32 = 2;
+
+/// `MIGRATIONS[n - 1]` upgrades a version `n` config object to version `n + 1`
+const MIGRATIONS: [fn(&mut Map<String, Value>); 1] = [migrate_v1_to_v2];
+
+/// Configs written before versioning: the derived `Default` stored font_size 0
+fn migrate_v1_to_v2(config: &mut Map<String, Value>) {
+    let font_size = config.get("font_size").and_then(Value::as_u64);
+    if !matches!(font_size, Some(8..=20)) {
+        config.insert("font_size".to_string(), Value::from(14));
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct UserConfig {
+    pub version: u32,
     pub download_dir: Option<String>,
     pub font_size: u8,
     pub remember_queue: bool,
-    #[serde(default = "default_use_system_binaries")]
     pub use_system_binaries: bool,
+    pub binary_sources: BinarySources,
+    pub binary_paths: BinaryPaths,
+    pub ytdlp_update_policy: YtDlpUpdatePolicy,
+    pub presets: BTreeMap<String, DownloadPreset>,
+    pub site_rules: Vec<SiteRule>,
+    /// Free space (in MiB) below which a download folder is reported as low on
+    /// space and running downloads are stopped; 0 disables disk space checks
+    pub low_disk_space_mb: u64,
+    /// Ask yt-dlp for the expected file size and refuse downloads that won't fit
+    pub check_download_size: bool,
+    /// Serve the local HTTP API on 127.0.0.1; port and token are in `api.json`
+    /// next to this file
+    pub api_enabled: bool,
+    pub clipboard: ClipboardSettings,
+}
+
+/// What to do when a download fails with an extractor error and bundled yt-dlp is in use
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum YtDlpUpdatePolicy {
+    Never,
+    /// Fail with `DownloadFailure::update_suggested` so the UI can offer the update
+    #[default]
+    Ask,
+    /// Update yt-dlp and retry the download once
+    Auto,
+}
+
+/// What to do with video links copied to the clipboard
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardMode {
+    /// Don't watch the clipboard
+    #[default]
+    Off,
+    /// Emit `clipboard-url-detected` so the UI can offer the link
+    Notify,
+    /// Add the link to the queue with `ClipboardSettings::preset`
+    Enqueue,
 }
 
-fn default_use_system_binaries() -> bool {
-    true
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct ClipboardSettings {
+    pub mode: ClipboardMode,
+    /// Preset for enqueued links, site rules and defaults apply when unset
+    pub preset: Option<String>,
+    /// Pick up links to any http(s) site, not only well-known video sites and site rule matches
+    pub any_site: bool,
+}
+
+/// Explicit absolute binary locations, taking priority over bundled and system binaries
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct BinaryPaths {
+    pub yt_dlp: Option<String>,
+    pub ffmpeg: Option<String>,
+    pub ffprobe: Option<String>,
+}
+
+/// Release channel yt-dlp is installed from
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum YtDlpChannel {
+    #[default]
+    Stable,
+    Nightly,
+    Master,
+}
+
+/// Where bundled binaries are downloaded from.
+///
+/// A mirror is either an `http(s)://` base URL or a local directory (plain path
+/// or `file://` URL) holding the release assets under their upstream file names,
+/// e.g. `<mirror>/yt-dlp_linux_aarch64`, or `<mirror>/<tag>/yt-dlp_linux_aarch64` when
+/// pinned. macOS ffmpeg archives are named `ffmpeg.zip` / `ffprobe.zip` (Intel) and
+/// `ffmpeg-arm64.zip` / `ffprobe-arm64.zip` (Apple Silicon).
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct BinarySources {
+    pub ytdlp_channel: YtDlpChannel,
+    /// Release tag to pin yt-dlp to (e.g. "2025.01.15"), latest when unset
+    pub ytdlp_version: Option<String>,
+    pub ytdlp_mirror: Option<String>,
+    pub ffmpeg_mirror: Option<String>,
 }
 
 impl UserConfig {
     pub fn new() -> Self {
         Self {
+            version: CONFIG_VERSION,
             download_dir: None,
             font_size: 14,
             remember_queue: true,
             use_system_binaries: true,
+            binary_sources: BinarySources::default(),
+            binary_paths: BinaryPaths::default(),
+            ytdlp_update_policy: YtDlpUpdatePolicy::default(),
+            presets: BTreeMap::new(),
+            site_rules: Vec::new(),
+            low_disk_space_mb: 1024,
+            check_download_size: true,
+            api_enabled: false,
+            clipboard: ClipboardSettings::default(),
         }
     }
+
+    pub fn low_disk_space_bytes(&self) -> u64 {
+        self.low_disk_space_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Outcome of reading a stored config
+struct ParsedConfig {
+    config: UserConfig,
+    /// Stored file should be rewritten (migrated or repaired)
+    rewrite: bool,
+    /// Stored file lost data and should be backed up before rewriting
+    backup: bool,
+    warnings: Vec<String>,
+}
+
+/// Config loaded once at startup and shared as managed state. Updates hold the
+/// lock while writing to disk so concurrent writers can't interleave.
+pub struct ConfigState(Mutex<UserConfig>);
+
+/// Payload of the `config-changed` event
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ConfigChange {
+    /// Changed settings as dotted paths, e.g. `font_size` or `binary_sources.ytdlp_channel`
+    pub keys: Vec<String>,
+    pub config: UserConfig,
+}
+
+impl ConfigState {
+    pub fn new(config: UserConfig) -> Self {
+        Self(Mutex::new(config))
+    }
+
+    pub fn get(&self) -> UserConfig {
+        self.0.lock().unwrap().clone()
+    }
+
+    // Persist a new config, keeping the old one if validation or writing fails
+    pub fn replace(&self, app_handle: &tauri::AppHandle, config: UserConfig) -> Result<UserConfig, String> {
+        self.update(app_handle, |current| {
+            *current = config;
+            Ok(())
+        })
+    }
+
+    // Apply a JSON merge patch (RFC 7396); null resets a setting to its default
+    pub fn patch(&self, app_handle: &tauri::AppHandle, patch: &Value) -> Result<UserConfig, String> {
+        if !patch.is_object() {
+            return Err("Settings patch must be a JSON object".to_string());
+        }
+
+        self.update(app_handle, |config| {
+            let mut value = serde_json::to_value(&*config)
+                .map_err(|e| format!("Failed to serialize config: {}", e))?;
+            merge_patch(&mut value, patch);
+
+            let mut patched: UserConfig = serde_json::from_value(value)
+                .map_err(|e| format!("Invalid settings: {}", e))?;
+            patched.version = config.version;
+            *config = patched;
+            Ok(())
+        })
+    }
+
+    // Apply a change, validate and persist it, then emit `config-changed`
+    pub fn update<F>(&self, app_handle: &tauri::AppHandle, change: F) -> Result<UserConfig, String>
+    where
+        F: FnOnce(&mut UserConfig) -> Result<(), String>,
+    {
+        use tauri::Emitter;
+
+        let mut current = self.0.lock().unwrap();
+        let mut config = current.clone();
+        change(&mut config)?;
+        ConfigManager::validate_config(&current, &config)?;
+
+        let keys = ConfigManager::changed_keys(&current, &config);
+        if keys.is_empty() {
+            return Ok(config);
+        }
+
+        ConfigManager::save_config(&app_handle.state::<AppPaths>(), &config)?;
+        *current = config.clone();
+        drop(current);
+
+        let _ = app_handle.emit("config-changed", ConfigChange { keys, config: config.clone() });
+        Ok(config)
+    }
+}
+
+/// RFC 7396 JSON merge patch: objects merge recursively, null removes, anything else replaces
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.as_str()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+// Collect dotted paths of values that differ between two JSON trees
+fn diff_keys(old: &Value, new: &Value, path: &str, keys: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let all: std::collections::BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in all {
+                let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                diff_keys(
+                    old.get(key).unwrap_or(&Value::Null),
+                    new.get(key).unwrap_or(&Value::Null),
+                    &child,
+                    keys,
+                );
+            }
+        }
+        _ if old != new => keys.push(path.to_string()),
+        _ => {}
+    }
+}
+
+/// Writes a file via a synced temp file and rename, so a crash leaves either the
+/// old or the new content and never a truncated file
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    write_atomic_file(path, content, false)
+}
+
+/// `write_atomic` for secrets: on Unix the file is only ever readable by its owner
+pub fn write_atomic_private(path: &Path, content: &[u8]) -> Result<(), String> {
+    write_atomic_file(path, content, true)
+}
+
+fn write_atomic_file(path: &Path, content: &[u8], private: bool) -> Result<(), String> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid file path: {}", path.display()))?;
+    let mut tmp_name = file_name.to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        // The mode only applies to new files, a leftover temp file keeps its own
+        let _ = fs::remove_file(&tmp_path);
+        options.create_new(true).mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+
+    let mut file = options
+        .open(&tmp_path)
+        .map_err(|e| format!("Failed to create {}: {}", tmp_path.display(), e))?;
+    file.write_all(content)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+    drop(file);
+
+    fs::rename(&tmp_path, path)
+        .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
+
+    // Persist the rename itself
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = fs::File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    Ok(())
 }
 
 pub struct ConfigManager;
 
 impl ConfigManager {
-    fn get_config_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
-        let config_dir = app_handle.path()
-            .app_config_dir()
-            .map_err(|e| format!("Failed to get app config directory: {}", e))?;
-        
+    fn get_config_path(paths: &AppPaths) -> Result<PathBuf, String> {
         // Create config directory if it doesn't exist
-        if !config_dir.exists() {
-            fs::create_dir_all(&config_dir)
+        if !paths.config_dir.exists() {
+            fs::create_dir_all(&paths.config_dir)
                 .map_err(|e| format!("Failed to create config directory: {}", e))?;
         }
         
-        Ok(config_dir.join("config.json"))
+        Ok(paths.config_dir.join("config.json"))
     }
 
-    pub fn load_config(app_handle: &tauri::AppHandle) -> Result<UserConfig, String> {
-        let config_path = Self::get_config_path(app_handle)?;
+    /// Load, migrate and repair config.json, returning warnings for the user
+    pub fn load_config(paths: &AppPaths) -> Result<(UserConfig, Vec<String>), String> {
+        let config_path = Self::get_config_path(paths)?;
         
         if !config_path.exists() {
-            return Ok(UserConfig::new());
+            return Ok((UserConfig::new(), Vec::new()));
         }
         
         let content = fs::read_to_string(&config_path)
             .map_err(|e| format!("Failed to read config file: {}", e))?;
         
-        let config: UserConfig = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse config file: {}", e))?;
-        
-        Ok(config)
+        let parsed = Self::parse_config(&content).unwrap_or_else(|e| ParsedConfig {
+            config: UserConfig::new(),
+            rewrite: true,
+            backup: true,
+            warnings: vec![format!("Settings file is corrupt ({}), starting from defaults", e)],
+        });
+
+        let mut warnings = parsed.warnings;
+
+        if parsed.backup {
+            match Self::backup_config(&config_path) {
+                Ok(backup) => warnings.push(format!("Previous settings saved to {}", backup.display())),
+                Err(e) => warnings.push(e),
+            }
+        }
+
+        if parsed.rewrite {
+            if let Err(e) = Self::save_config(paths, &parsed.config) {
+                warnings.push(e);
+            }
+        }
+
+        for warning in &warnings {
+            eprintln!("Warning: {}", warning);
+        }
+
+        Ok((parsed.config, warnings))
+    }
+
+    /// Bring a stored settings object up to `CONFIG_VERSION`, returning whether
+    /// anything was migrated
+    pub fn migrate_config(stored: &mut Map<String, Value>, warnings: &mut Vec<String>) -> bool {
+        // Configs from before versioning have no version field
+        let version = stored.get("version").and_then(Value::as_u64).unwrap_or(1) as u32;
+        if version > CONFIG_VERSION {
+            warnings.push(format!(
+                "Settings were written by a newer version of the app (schema {}), unknown settings will be ignored",
+                version
+            ));
+            return false;
+        }
+
+        let mut migrated = false;
+        for migration in MIGRATIONS.iter().skip(version.saturating_sub(1) as usize) {
+            migration(stored);
+            migrated = true;
+        }
+        stored.insert("version".to_string(), Value::from(CONFIG_VERSION));
+        migrated
+    }
+
+    // Migrate a stored config to the current version, resetting fields that fail to parse
+    fn parse_config(content: &str) -> Result<ParsedConfig, String> {
+        let mut stored = match serde_json::from_str::<Value>(content).map_err(|e| e.to_string())? {
+            Value::Object(map) => map,
+            _ => return Err("expected a JSON object".to_string()),
+        };
+
+        let mut warnings = Vec::new();
+        let rewrite = Self::migrate_config(&mut stored, &mut warnings);
+
+        if let Ok(config) = serde_json::from_value::<UserConfig>(Value::Object(stored.clone())) {
+            return Ok(ParsedConfig { config, rewrite, backup: false, warnings });
+        }
+
+        // Keep every field that parses on its own, the rest fall back to defaults
+        let mut salvaged = Map::new();
+        let mut reset = Vec::new();
+        for (key, value) in stored {
+            let mut candidate = salvaged.clone();
+            candidate.insert(key.clone(), value);
+            if serde_json::from_value::<UserConfig>(Value::Object(candidate.clone())).is_ok() {
+                salvaged = candidate;
+            } else {
+                reset.push(key);
+            }
+        }
+
+        let config = serde_json::from_value(Value::Object(salvaged)).map_err(|e| e.to_string())?;
+        warnings.push(format!("Invalid settings reset to defaults: {}", reset.join(", ")));
+
+        Ok(ParsedConfig { config, rewrite: true, backup: true, warnings })
+    }
+
+    // Copy config.json aside before overwriting data we couldn't read
+    fn backup_config(config_path: &Path) -> Result<PathBuf, String> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let backup_path = config_path.with_file_name(format!("config.corrupt-{}.json", timestamp));
+
+        fs::copy(config_path, &backup_path)
+            .map_err(|e| format!("Failed to back up config file: {}", e))?;
+
+        Ok(backup_path)
     }
 
-    pub fn save_config(app_handle: &tauri::AppHandle, config: &UserConfig) -> Result<(), String> {
-        let config_path = Self::get_config_path(app_handle)?;
+    pub fn save_config(paths: &AppPaths, config: &UserConfig) -> Result<(), String> {
+        let config_path = Self::get_config_path(paths)?;
         
         let content = serde_json::to_string_pretty(config)
             .map_err(|e| format!("Failed to serialize config: {}", e))?;
         
-        fs::write(&config_path, content)
+        write_atomic(&config_path, content.as_bytes())
             .map_err(|e| format!("Failed to write config file: {}", e))?;
         
         Ok(())
     }
 
-    pub fn get_download_dir(app_handle: &tauri::AppHandle) -> Result<String, String> {
-        let config = Self::load_config(app_handle)?;
-        
-        if let Some(dir) = config.download_dir {
-            // Validate that the directory exists and is writable
-            let path = PathBuf::from(&dir);
-            if path.exists() && path.is_dir() {
-                // Check if writable by trying to create a temp file
-                let test_file = path.join(".ytdl_test");
-                if fs::write(&test_file, "test").is_ok() {
-                    let _ = fs::remove_file(&test_file);
-                    return Ok(dir);
+    // Reject settings the app can't work with
+    fn validate_config(old: &UserConfig, new: &UserConfig) -> Result<(), String> {
+        if !(8..=20).contains(&new.font_size) {
+            return Err(format!("Font size must be between 8 and 20, got {}", new.font_size));
+        }
+
+        for (name, preset) in &new.presets {
+            preset.validate().map_err(|e| format!("Preset \"{}\": {}", name, e))?;
+        }
+
+        for rule in &new.site_rules {
+            rule.validate(&new.presets)
+                .map_err(|e| format!("Site rule \"{}\": {}", rule.name, e))?;
+        }
+
+        // Only check the download directory when it changes, so a folder that has
+        // since disappeared doesn't block saving unrelated settings
+        if new.download_dir != old.download_dir {
+            if let Some(ref dir) = new.download_dir {
+                let validation = Storage::validate_dir(dir, 0);
+                if !validation.is_usable() {
+                    return Err(validation.message);
                 }
             }
         }
+
+        Ok(())
+    }
+
+    fn changed_keys(old: &UserConfig, new: &UserConfig) -> Vec<String> {
+        let mut keys = Vec::new();
+        if let (Ok(old), Ok(new)) = (serde_json::to_value(old), serde_json::to_value(new)) {
+            diff_keys(&old, &new, "", &mut keys);
+        }
+        keys
+    }
+
+    pub fn get_download_dir(app_handle: &tauri::AppHandle) -> Result<String, String> {
+        use tauri::Emitter;
+
+        let config = app_handle.state::<ConfigState>().get();
+        let (dir, rejected) = Self::resolve_download_dir(&config)?;
+
+        // Let the UI tell the user their folder is being ignored and offer to fix it
+        if let Some(validation) = rejected {
+            let _ = app_handle.emit("download-dir-invalid", validation);
+        }
+
+        Ok(dir)
+    }
+
+    /// The configured download folder, or ~/Downloads along with the reason the
+    /// configured one can't be used
+    pub fn resolve_download_dir(config: &UserConfig) -> Result<(String, Option<DirValidation>), String> {
+        if let Some(ref dir) = config.download_dir {
+            let validation = Storage::validate_dir(dir, config.low_disk_space_bytes());
+            if validation.is_usable() {
+                return Ok((dir.clone(), None));
+            }
+            return Ok((Self::get_default_download_dir()?, Some(validation)));
+        }
         
+        Ok((Self::get_default_download_dir()?, None))
+    }
+
+    fn get_default_download_dir() -> Result<String, String> {
         // Fallback to default downloads directory
         let home_dir = dirs_next::home_dir()
             .ok_or("Failed to get home directory")?;
@@ -0,0 +1,288 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+use crate::config::{write_atomic, ConfigManager, ConfigState};
+
+/// yt-dlp switches imported site rules may pass. Anything else is refused: many
+/// options run commands or write files (`--exec`, `--use-postprocessor Exec:…`,
+/// `--config-locations`, `--plugin-dirs`, `--downloader-args`, …) and a blocklist
+/// can't keep up with new ones.
+const SAFE_FLAGS: [&str; 34] = [
+    "--abort-on-error", "--audio-multistreams", "--check-formats", "--embed-chapters",
+    "--embed-metadata", "--embed-subs", "--embed-thumbnail", "--force-overwrites",
+    "--geo-bypass", "--hls-use-mpegts", "--ignore-errors", "-i", "--keep-video",
+    "--live-from-start", "--no-abort-on-error", "--no-check-certificates", "--no-embed-subs",
+    "--no-embed-thumbnail", "--no-keep-video", "--no-live-from-start", "--no-mtime",
+    "--no-overwrites", "--no-part", "--no-playlist", "--prefer-free-formats",
+    "--restrict-filenames", "--video-multistreams", "--windows-filenames", "--write-auto-subs",
+    "--write-description", "--write-subs", "--write-thumbnail", "--xattrs", "--yes-playlist",
+];
+
+/// yt-dlp options taking a value that imported site rules may pass, see `SAFE_FLAGS`
+const SAFE_VALUE_OPTIONS: [&str; 34] = [
+    "--audio-format", "--audio-quality", "--concurrent-fragments", "-N", "--convert-subs",
+    "--date", "--dateafter", "--datebefore", "--extractor-args", "--format", "-f",
+    "--format-sort", "-S", "--fragment-retries", "--geo-bypass-country", "--limit-rate", "-r",
+    "--match-filters", "--max-downloads", "--max-filesize", "--merge-output-format",
+    "--min-filesize", "--playlist-items", "-I", "--remux-video", "--retries", "-R",
+    "--sleep-interval", "--max-sleep-interval", "--sleep-requests", "--sponsorblock-mark",
+    "--sponsorblock-remove", "--sub-langs", "--throttled-rate",
+];
+
+/// Settings a bundle carries between machines
+const PORTABLE_KEYS: [&str; 6] = [
+    "font_size", "remember_queue", "presets", "site_rules", "low_disk_space_mb", "check_download_size",
+];
+
+/// Settings that only carry over when machine paths are asked for
+const MACHINE_PATH_KEYS: [&str; 2] = ["download_dir", "binary_paths"];
+
+/// Per-preset and per-rule keys that point into the local file system
+const ITEM_PATH_KEYS: [&str; 2] = ["download_dir", "output_template"];
+
+/// Result of `import_config`
+#[derive(Serialize, Clone)]
+pub struct ImportSummary {
+    pub presets: usize,
+    pub site_rules: usize,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BundleFormat {
+    Json,
+    Toml,
+}
+
+impl BundleFormat {
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => BundleFormat::Toml,
+            _ => BundleFormat::Json,
+        }
+    }
+}
+
+/// Portable settings bundle: the `PORTABLE_KEYS` of `UserConfig`, plus download
+/// folders, output templates and binary locations when they are asked for.
+/// Binary sources, update policy, the API and the clipboard monitor never
+/// travel, a shared file must not be able to change where binaries come from or
+/// open a port.
+pub struct ConfigBundle;
+
+impl ConfigBundle {
+    // Keep the settings that make sense on another machine, returns the names of the dropped ones
+    fn portable_settings(settings: &mut Map<String, Value>, include_machine_paths: bool) -> Vec<String> {
+        let mut dropped = Vec::new();
+        settings.retain(|key, _| {
+            let keep = PORTABLE_KEYS.contains(&key.as_str())
+                || (include_machine_paths && MACHINE_PATH_KEYS.contains(&key.as_str()));
+            if !keep {
+                dropped.push(key.clone());
+            }
+            keep
+        });
+
+        if !include_machine_paths {
+            let items = settings.iter_mut().flat_map(|(key, value)| match (key.as_str(), value) {
+                ("presets", Value::Object(presets)) => presets.values_mut().collect(),
+                ("site_rules", Value::Array(rules)) => rules.iter_mut().collect(),
+                _ => Vec::new(),
+            });
+            for item in items.filter_map(Value::as_object_mut) {
+                item.retain(|key, _| !ITEM_PATH_KEYS.contains(&key.as_str()));
+            }
+        }
+
+        dropped
+    }
+
+    // TOML has no null, unset optional settings are simply left out
+    fn strip_nulls(value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                map.retain(|_, v| !v.is_null());
+                map.values_mut().for_each(Self::strip_nulls);
+            }
+            Value::Array(items) => items.iter_mut().for_each(Self::strip_nulls),
+            _ => {}
+        }
+    }
+
+    pub fn export(config: &ConfigState, path: &Path, include_machine_paths: bool) -> Result<(), String> {
+        let mut settings = match serde_json::to_value(config.get()) {
+            Ok(Value::Object(map)) => map,
+            Ok(_) => return Err("Failed to serialize config".to_string()),
+            Err(e) => return Err(format!("Failed to serialize config: {}", e)),
+        };
+
+        Self::portable_settings(&mut settings, include_machine_paths);
+
+        let mut settings = Value::Object(settings);
+        Self::strip_nulls(&mut settings);
+
+        let content = match BundleFormat::from_path(path) {
+            BundleFormat::Json => serde_json::to_string_pretty(&settings)
+                .map_err(|e| format!("Failed to serialize settings: {}", e))?,
+            BundleFormat::Toml => toml::Value::try_from(&settings)
+                .and_then(|value| toml::to_string_pretty(&value))
+                .map_err(|e| format!("Failed to serialize settings as TOML: {}", e))?,
+        };
+
+        write_atomic(path, content.as_bytes())
+            .map_err(|e| format!("Failed to write settings file: {}", e))
+    }
+
+    /// Merge a bundle into the current config. Presets are merged by name,
+    /// site rules and other settings in the bundle replace the current ones.
+    pub fn import(
+        app_handle: &tauri::AppHandle,
+        config: &ConfigState,
+        path: &Path,
+        include_machine_paths: bool,
+    ) -> Result<ImportSummary, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read settings file: {}", e))?;
+
+        let parsed = match BundleFormat::from_path(path) {
+            BundleFormat::Json => serde_json::from_str::<Value>(&content)
+                .map_err(|e| format!("Invalid settings file: {}", e))?,
+            BundleFormat::Toml => toml::from_str::<toml::Value>(&content)
+                .map_err(|e| format!("Invalid settings file: {}", e))
+                .and_then(|value| serde_json::to_value(value).map_err(|e| format!("Invalid settings file: {}", e)))?,
+        };
+
+        let Value::Object(mut settings) = parsed else {
+            return Err("Invalid settings file: expected a table of settings".to_string());
+        };
+
+        // Exports always carry a version, a bundle without one is hand-written
+        // against the current schema and must not be migrated as a pre-versioning
+        // config (that would reset font_size)
+        let mut warnings = Vec::new();
+        if settings.contains_key("version") {
+            ConfigManager::migrate_config(&mut settings, &mut warnings);
+        }
+        settings.remove("version");
+
+        let dropped = Self::portable_settings(&mut settings, include_machine_paths);
+        if !dropped.is_empty() {
+            warnings.push(format!("Ignored settings that stay on this machine: {}", dropped.join(", ")));
+        }
+
+        Self::check_site_rule_args(&settings)?;
+
+        let presets = settings.get("presets").and_then(Value::as_object).map_or(0, Map::len);
+        let site_rules = settings.get("site_rules").and_then(Value::as_array).map_or(0, Vec::len);
+
+        config.patch(app_handle, &Value::Object(settings))?;
+
+        Ok(ImportSummary { presets, site_rules, warnings })
+    }
+
+    // A shared bundle must not be able to make downloads run commands or write files
+    fn check_site_rule_args(settings: &Map<String, Value>) -> Result<(), String> {
+        let Some(Value::Array(rules)) = settings.get("site_rules") else {
+            return Ok(());
+        };
+
+        for rule in rules {
+            let args: Vec<&str> = rule
+                .get("extra_args")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            if let Some(arg) = Self::find_unsafe_arg(&args) {
+                let name = rule.get("name").and_then(Value::as_str).unwrap_or("unnamed");
+                return Err(format!(
+                    "Site rule \"{}\" passes {} to yt-dlp, which imported settings may not use. Refusing to import it.",
+                    name, arg
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The first argument that isn't an allowed option or the value of one
+    fn find_unsafe_arg<'a>(args: &[&'a str]) -> Option<&'a str> {
+        let mut args = args.iter();
+        while let Some(&arg) = args.next() {
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, _)) if option.starts_with("--") => (option, true),
+                _ => (arg, false),
+            };
+
+            if SAFE_VALUE_OPTIONS.contains(&option) {
+                if !inline_value && args.next().is_none() {
+                    return Some(arg);
+                }
+            } else if inline_value || !SAFE_FLAGS.contains(&option) {
+                return Some(arg);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn portable(bundle: Value, include_machine_paths: bool) -> (Value, Vec<String>) {
+        let Value::Object(mut settings) = bundle else { unreachable!() };
+        let dropped = ConfigBundle::portable_settings(&mut settings, include_machine_paths);
+        (Value::Object(settings), dropped)
+    }
+
+    #[test]
+    fn machine_local_settings_are_ignored() {
+        let bundle = json!({
+            "font_size": 18,
+            "api_enabled": true,
+            "binary_sources": { "ytdlp_mirror": "https://evil.example/releases" },
+            "use_system_binaries": true,
+            "ytdlp_update_policy": "auto",
+            "clipboard": { "mode": "enqueue" },
+        });
+
+        for include_machine_paths in [false, true] {
+            let (settings, mut dropped) = portable(bundle.clone(), include_machine_paths);
+            assert_eq!(settings, json!({ "font_size": 18 }));
+            dropped.sort();
+            assert_eq!(
+                dropped,
+                ["api_enabled", "binary_sources", "clipboard", "use_system_binaries", "ytdlp_update_policy"]
+            );
+        }
+    }
+
+    #[test]
+    fn paths_and_templates_need_opt_in() {
+        let bundle = json!({
+            "download_dir": "/home/me/Videos",
+            "binary_paths": { "yt_dlp": "/opt/yt-dlp" },
+            "presets": { "music": { "audio_only": true, "download_dir": "/music", "output_template": "/tmp/%(title)s" } },
+            "site_rules": [{ "pattern": "*.example.com", "download_dir": "/rules", "output_template": "x" }],
+        });
+
+        let (settings, mut dropped) = portable(bundle.clone(), false);
+        assert_eq!(
+            settings,
+            json!({
+                "presets": { "music": { "audio_only": true } },
+                "site_rules": [{ "pattern": "*.example.com" }],
+            })
+        );
+        dropped.sort();
+        assert_eq!(dropped, ["binary_paths", "download_dir"]);
+
+        let (settings, dropped) = portable(bundle.clone(), true);
+        assert_eq!(settings, bundle);
+        assert!(dropped.is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use crate::config::ConfigState;
+use crate::presets::DownloadPreset;
+use crate::queue::{DownloadQueue, QueuedDownload};
+
+pub const SCHEME: &str = "ezdl";
+
+const MAX_URL_LEN: usize = 2048;
+/// Unanswered requests kept, so a page opening links in a loop can't pile up dialogs
+const MAX_PENDING: usize = 20;
+
+/// A validated `ezdl://download?url=…&preset=…` link waiting for the user to confirm it
+#[derive(Serialize, Clone, Debug)]
+pub struct DeepLinkRequest {
+    pub id: u64,
+    pub url: String,
+    pub preset: Option<String>,
+}
+
+/// Payload of `deep-link-rejected`
+#[derive(Serialize, Clone, Debug)]
+pub struct RejectedLink {
+    pub link: String,
+    pub message: String,
+}
+
+/// Links opened from web pages and other apps. Nothing is downloaded until the UI
+/// confirms a request, so a page can't start downloads on its own.
+#[derive(Default)]
+pub struct DeepLinks {
+    pending: Mutex<Vec<DeepLinkRequest>>,
+    next_id: Mutex<u64>,
+}
+
+impl DeepLinks {
+    /// Check a link and return the video URL and preset it asks for
+    pub fn parse(link: &url::Url, presets: &BTreeMap<String, DownloadPreset>) -> Result<(String, Option<String>), String> {
+        if link.scheme() != SCHEME {
+            return Err(format!("Not an {}:// link", SCHEME));
+        }
+        if link.host_str() != Some("download") || !matches!(link.path(), "" | "/") {
+            return Err("Unknown action, expected ezdl://download".to_string());
+        }
+
+        let mut url = None;
+        let mut preset = None;
+        for (key, value) in link.query_pairs() {
+            let slot = match key.as_ref() {
+                "url" => &mut url,
+                "preset" => &mut preset,
+                _ => return Err(format!("Unknown parameter \"{}\"", key)),
+            };
+            if slot.replace(value.into_owned()).is_some() {
+                return Err(format!("Parameter \"{}\" given more than once", key));
+            }
+        }
+
+        let url = url.ok_or("Missing url parameter")?;
+        if url.len() > MAX_URL_LEN {
+            return Err("URL is too long".to_string());
+        }
+        let parsed = url::Url::parse(&url).map_err(|_| format!("Invalid URL: {}", url))?;
+        if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+            return Err(format!("Only http and https URLs can be downloaded: {}", url));
+        }
+
+        // An unknown preset would silently fall back to defaults
+        let preset = preset.filter(|name| !name.trim().is_empty());
+        if let Some(ref name) = preset {
+            if !presets.contains_key(name) {
+                return Err(format!("Unknown preset \"{}\"", name));
+            }
+        }
+
+        Ok((parsed.to_string(), preset))
+    }
+
+    /// Validate opened links and ask the UI to confirm each one
+    pub fn handle(app_handle: &tauri::AppHandle, links: Vec<url::Url>) {
+        let config = app_handle.state::<ConfigState>().get();
+        let state = app_handle.state::<DeepLinks>();
+
+        for link in links {
+            match Self::parse(&link, &config.presets).and_then(|(url, preset)| state.add_pending(url, preset)) {
+                Ok(Some(request)) => {
+                    let _ = app_handle.emit("deep-link-confirm", request);
+                }
+                // Already waiting for an answer
+                Ok(None) => {}
+                Err(message) => {
+                    eprintln!("Warning: Rejected link {}: {}", link, message);
+                    let _ = app_handle.emit("deep-link-rejected", RejectedLink {
+                        link: link.to_string(),
+                        message,
+                    });
+                }
+            }
+        }
+    }
+
+    // Record a request for the UI to confirm, None when the same one is already pending
+    fn add_pending(&self, url: String, preset: Option<String>) -> Result<Option<DeepLinkRequest>, String> {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.iter().any(|request| request.url == url && request.preset == preset) {
+            return Ok(None);
+        }
+        if pending.len() >= MAX_PENDING {
+            return Err("Too many links are waiting for confirmation".to_string());
+        }
+
+        let mut next_id = self.next_id.lock().unwrap();
+        *next_id += 1;
+        let request = DeepLinkRequest { id: *next_id, url, preset };
+        pending.push(request.clone());
+        Ok(Some(request))
+    }
+
+    /// Requests not yet answered, including those that arrived before the UI loaded
+    pub fn pending(&self) -> Vec<DeepLinkRequest> {
+        self.pending.lock().unwrap().clone()
+    }
+
+    /// Answer a request, enqueueing and starting the download when accepted
+    pub fn resolve(&self, app_handle: &tauri::AppHandle, id: u64, accept: bool) -> Result<(), String> {
+        let request = {
+            let mut pending = self.pending.lock().unwrap();
+            let index = pending
+                .iter()
+                .position(|request| request.id == id)
+                .ok_or_else(|| format!("No pending link with id {}", id))?;
+            pending.remove(index)
+        };
+
+        if accept {
+            DownloadQueue::enqueue(app_handle, vec![QueuedDownload {
+                url: request.url,
+                title: None,
+                preset: request.preset,
+                download_dir: None,
+                source: "link".to_string(),
+            }], true);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(link: &str) -> Result<(String, Option<String>), String> {
+        let presets = BTreeMap::from([("music".to_string(), DownloadPreset::default())]);
+        DeepLinks::parse(&url::Url::parse(link).unwrap(), &presets)
+    }
+
+    #[test]
+    fn parses_download_links() {
+        assert_eq!(
+            parse("ezdl://download?url=https%3A%2F%2Fwww.youtube.com%2Fwatch%3Fv%3DdQw4w9WgXcQ&preset=music"),
+            Ok(("https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(), Some("music".to_string())))
+        );
+        assert_eq!(
+            parse("ezdl://download/?url=https://vimeo.com/76979871&preset="),
+            Ok(("https://vimeo.com/76979871".to_string(), None))
+        );
+    }
+
+    #[test]
+    fn rejects_other_schemes_and_actions() {
+        assert!(parse("https://download?url=https://vimeo.com/76979871").is_err());
+        assert!(parse("ezdl://delete?url=https://vimeo.com/76979871").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_parameters() {
+        assert_eq!(parse("ezdl://download"), Err("Missing url parameter".to_string()));
+        assert!(parse("ezdl://download?url=https://a.example&url=https://b.example").is_err());
+        assert!(parse("ezdl://download?url=https://vimeo.com/76979871&exec=1").is_err());
+        assert!(parse("ezdl://download?url=https://vimeo.com/76979871&preset=unknown").is_err());
+    }
+
+    #[test]
+    fn rejects_non_web_targets() {
+        for target in ["file:///etc/passwd", "javascript:alert(1)", "ftp://example.com/video.mp4", "--exec=id"] {
+            let link = format!("ezdl://download?url={}", url::form_urlencoded::byte_serialize(target.as_bytes()).collect::<String>());
+            assert!(parse(&link).is_err(), "{} was accepted", target);
+        }
+    }
+
+    #[test]
+    fn pending_requests_are_deduplicated_and_capped() {
+        let links = DeepLinks::default();
+        let first = links.add_pending("https://vimeo.com/1".to_string(), None).unwrap().unwrap();
+        assert_eq!(first.id, 1);
+        assert!(links.add_pending("https://vimeo.com/1".to_string(), None).unwrap().is_none());
+        assert!(links.add_pending("https://vimeo.com/1".to_string(), Some("music".to_string())).unwrap().is_some());
+
+        for index in 2..MAX_PENDING {
+            links.add_pending(format!("https://vimeo.com/{}", index), None).unwrap();
+        }
+        assert_eq!(links.pending().len(), MAX_PENDING);
+        assert!(links.add_pending("https://vimeo.com/overflow".to_string(), None).is_err());
+    }
+}
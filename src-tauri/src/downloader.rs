@@ -0,0 +1,718 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::Manager;
+use crate::archive::DownloadArchive;
+use crate::binary_manager::{BinaryManager, BinarySource, BinaryStatus, BinaryStatusCache};
+use crate::config::{ConfigManager, ConfigState, UserConfig, YtDlpUpdatePolicy};
+use crate::history::{self, HistoryStore};
+use crate::paths::AppPaths;
+use crate::presets::{PresetManager, PresetOverrides};
+use crate::site_rules::{SiteRule, SiteRules};
+use crate::storage::{DirValidation, DiskLow, DiskMonitor, Storage};
+
+/// The app's own download, cancelled by `cancel_download`
+pub static CURRENT_PROCESS: ProcessSlot = ProcessSlot::new();
+
+/// Where a running yt-dlp registers its process ID so another thread can cancel it
+pub struct ProcessSlot(Mutex<Option<Arc<Mutex<Option<u32>>>>>);
+
+/// Receives yt-dlp output while a download runs: Tauri events in the GUI,
+/// the terminal on the command line
+pub trait DownloadObserver: Clone + Send + 'static {
+    fn log(&self, line: &str);
+    fn progress(&self, percent: u8);
+
+    /// The configured download folder is unusable and the fallback is used
+    fn dir_invalid(&self, _validation: &DirValidation) {}
+
+    /// Free space ran out before or during the download
+    fn disk_low(&self, _disk_low: &DiskLow) {}
+
+    /// The download failed after yt-dlp was found and the arguments built
+    fn error(&self, _message: &str) {}
+
+    /// Checked before yt-dlp starts, to skip downloads cancelled while preparing
+    fn cancelled(&self) -> bool {
+        false
+    }
+}
+
+impl DownloadObserver for tauri::Window {
+    fn log(&self, line: &str) {
+        use tauri::Emitter;
+        let _ = self.emit("download-log", line.to_string());
+    }
+
+    fn progress(&self, percent: u8) {
+        use tauri::Emitter;
+        let _ = self.emit("download-progress", percent);
+    }
+
+    // Lets the UI tell the user their folder is being ignored and offer to fix it
+    fn dir_invalid(&self, validation: &DirValidation) {
+        use tauri::Emitter;
+        let _ = self.emit("download-dir-invalid", validation);
+    }
+
+    fn disk_low(&self, disk_low: &DiskLow) {
+        use tauri::Emitter;
+        let _ = self.emit("disk-low", disk_low);
+    }
+
+    fn error(&self, message: &str) {
+        use tauri::Emitter;
+        let _ = self.emit("download-error", message.to_string());
+    }
+}
+
+/// What to download and how, as given by the queue or the command line
+pub struct DownloadRequest {
+    pub url: String,
+    /// Explicit folder, overriding the preset's and the configured one
+    pub f_path: Option<String>,
+    pub preset: Option<String>,
+    pub overrides: PresetOverrides,
+}
+
+/// Result of `download_url` and payload of `download-complete`
+#[derive(serde::Serialize, Clone)]
+pub struct DownloadResult {
+    pub code: i32,
+    /// Final paths of the downloaded files
+    pub files: Vec<String>,
+}
+
+/// Error of `download_url`
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct DownloadFailure {
+    pub message: String,
+    /// yt-dlp's exit code, when it ran and failed
+    pub code: Option<i32>,
+    /// yt-dlp hit an extractor failure and `YtDlpUpdatePolicy::Ask` leaves the update to the user
+    pub update_suggested: bool,
+    /// Free space ran out: queues pause instead of moving on to their next download
+    pub disk_low: Option<DiskLow>,
+}
+
+impl From<String> for DownloadFailure {
+    fn from(message: String) -> Self {
+        Self { message, code: None, update_suggested: false, disk_low: None }
+    }
+}
+
+/// Settings, binaries and history a download runs with, from the app's state
+/// or loaded by the command line
+pub struct DownloadContext<'a> {
+    pub paths: &'a AppPaths,
+    pub config: UserConfig,
+    pub status: BinaryStatus,
+    pub history: &'a HistoryStore,
+    /// Lets `YtDlpUpdatePolicy::Auto` update yt-dlp; the command line leaves updates to the app
+    pub app_handle: Option<&'a tauri::AppHandle>,
+    /// Passed on to `run_ytdlp`
+    pub new_process_group: bool,
+}
+
+impl<'a> DownloadContext<'a> {
+    pub fn app(app_handle: &'a tauri::AppHandle) -> Result<Self, String> {
+        Ok(Self {
+            paths: app_handle.state::<AppPaths>().inner(),
+            config: app_handle.state::<ConfigState>().get(),
+            status: app_handle.state::<BinaryStatusCache>().get(app_handle)?,
+            history: app_handle.state::<HistoryStore>().inner(),
+            app_handle: Some(app_handle),
+            new_process_group: true,
+        })
+    }
+}
+
+/// A download with its settings resolved and yt-dlp arguments built
+pub struct PreparedDownload {
+    pub url: String,
+    pub ytdlp_path: String,
+    /// yt-dlp options, without the history record ones and the URL added by `run_args`
+    pub args: Vec<String>,
+    /// Download folder
+    pub f_path: String,
+    pub preset_name: Option<String>,
+    /// Whole playlists are downloaded
+    pub playlist: bool,
+    /// Name of the site rule that applied
+    pub rule: Option<String>,
+    /// Set when the configured download folder was unusable and ~/Downloads is used instead
+    pub rejected_dir: Option<DirValidation>,
+    pub warnings: Vec<String>,
+    record_file: PathBuf,
+    /// Scratch archive of a forced download and the preset's archive it is merged into
+    forced_archive: Option<(PathBuf, PathBuf)>,
+}
+
+impl PreparedDownload {
+    /// Arguments for the actual run, which also describe finished videos for the history
+    pub fn run_args(&self) -> Vec<String> {
+        let mut args = self.args.clone();
+        args.extend(HistoryStore::ytdlp_args(&self.record_file));
+        args.extend(["--".to_string(), self.url.clone()]);
+        args
+    }
+
+    /// Store videos finished since the last call in the history, and in the preset's
+    /// archive for forced downloads, returning their final paths
+    pub fn record_history<O: DownloadObserver>(&self, history: &HistoryStore, started_at: u64, observer: &O) -> Vec<String> {
+        if let Some((ref scratch, ref archive)) = self.forced_archive {
+            if let Err(e) = DownloadArchive::merge(archive, scratch) {
+                observer.log(&format!("Warning: {}", e));
+            }
+        }
+
+        let videos = HistoryStore::read_record(&self.record_file).unwrap_or_else(|e| {
+            observer.log(&format!("Warning: {}", e));
+            Vec::new()
+        });
+        if let Err(e) = history.add(&videos, &self.url, self.preset_name.as_deref(), started_at) {
+            observer.log(&format!("Warning: {}", e));
+        }
+        videos.into_iter().filter_map(|video| video.filepath).collect()
+    }
+}
+
+pub struct Downloader;
+
+impl Downloader {
+    /// Apply site rules and presets, check the toolchain and build yt-dlp arguments
+    pub fn prepare(
+        paths: &AppPaths,
+        config: &UserConfig,
+        status: &BinaryStatus,
+        request: DownloadRequest,
+    ) -> Result<PreparedDownload, String> {
+        let url = request.url;
+        if !is_web_url(&url) {
+            return Err(format!("Only http and https URLs can be downloaded: {}", url));
+        }
+        let mut warnings = Vec::new();
+
+        // Site rules fill in what the caller left unspecified
+        let rule = SiteRules::find(&config.site_rules, &url)?;
+        let preset_name = request.preset.or_else(|| rule.and_then(|rule| rule.preset.clone()));
+        let rule_overrides = rule.map(SiteRule::overrides).unwrap_or_default();
+
+        let preset = PresetManager::resolve(&config.presets, preset_name.as_deref(), rule_overrides)?
+            .with_overrides(request.overrides);
+        preset.validate()?;
+
+        if !status.yt_dlp_installed {
+            if config.use_system_binaries {
+                return Err("yt-dlp not found. Please install yt-dlp from your package manager or enable binary downloads in Settings.".to_string());
+            } else {
+                return Err("Bundled binaries not found. Please download binaries from File menu > Download Binaries.".to_string());
+            }
+        }
+
+        // Check the full toolchain each requested post-processing step runs
+        let steps = preset.required_tools();
+        for (step, tools) in &steps {
+            let missing = status.missing_tools(tools);
+            if missing.is_empty() {
+                continue;
+            }
+            let missing = missing.join(" and ");
+            if config.use_system_binaries {
+                return Err(format!("{} not found. {} requires {}. Please install it from your package manager.", missing, step, tools.join(" and ")));
+            } else {
+                return Err(format!("{} not downloaded. Please download binaries from File menu > Download Binaries.", missing));
+            }
+        }
+
+        let needs_ffprobe = steps.iter().any(|(_, tools)| tools.contains(&"ffprobe"));
+        if needs_ffprobe && !status.ffprobe_beside_ffmpeg() {
+            warnings.push("ffprobe is not in the same directory as ffmpeg, yt-dlp may not find it".to_string());
+        }
+
+        // Explicit folder from the queue item, then the preset's, then the configured default
+        let (f_path, rejected_dir) = match request.f_path.filter(|p| !p.trim().is_empty()).or_else(|| preset.download_dir.clone()) {
+            Some(dir) => (dir, None),
+            None => ConfigManager::resolve_download_dir(config)?,
+        };
+
+        let ytdlp_path = status.yt_dlp_path.clone().unwrap_or_else(|| "yt-dlp".to_string());
+
+        let output_template = format!("{}/{}", f_path, preset.output_template);
+
+        let mut args = status.yt_dlp_args.clone();
+        args.extend([
+            "--newline".to_string(),
+            "-o".to_string(), output_template,
+        ]);
+
+        // Pin yt-dlp to the resolved ffmpeg rather than letting it search PATH again
+        if let Some(ref ffmpeg_path) = status.ffmpeg_path {
+            args.push("--ffmpeg-location".to_string());
+            args.push(ffmpeg_path.clone());
+        }
+
+        args.extend(preset.ytdlp_args());
+
+        // Skip videos already fetched with this preset. Re-downloads on purpose get an
+        // empty archive instead, so what they fetch is still recorded afterwards.
+        let forced_archive = if preset.force_download {
+            let scratch = DownloadArchive::scratch_file();
+            args.extend(DownloadArchive::ytdlp_args_for(&scratch));
+            Some((scratch, DownloadArchive::path(paths, preset_name.as_deref())))
+        } else {
+            args.extend(DownloadArchive::ytdlp_args(paths, preset_name.as_deref())?);
+            None
+        };
+        if let Some(rule) = rule {
+            args.extend(rule.extra_args.iter().cloned());
+        }
+
+        Ok(PreparedDownload {
+            url,
+            ytdlp_path,
+            args,
+            f_path,
+            preset_name,
+            playlist: preset.playlist,
+            rule: rule.map(|rule| rule.name.clone()),
+            rejected_dir,
+            warnings,
+            record_file: HistoryStore::record_file(),
+            forced_archive,
+        })
+    }
+}
+
+impl Downloader {
+    /// Download with the app's settings: prepare, check disk space, run yt-dlp,
+    /// record the history and apply the yt-dlp update policy on extractor failures.
+    /// Shared by the UI queue, the local API and the command line.
+    pub async fn download<O: DownloadObserver>(
+        context: &DownloadContext<'_>,
+        observer: &O,
+        process: &'static ProcessSlot,
+        request: DownloadRequest,
+    ) -> Result<DownloadResult, DownloadFailure> {
+        let DownloadContext { paths, ref config, ref status, history, app_handle, new_process_group } = *context;
+        let prepared = Self::prepare(paths, config, status, request)?;
+
+        if let Some(ref rule) = prepared.rule {
+            observer.log(&format!("Applying site rule \"{}\"", rule));
+        }
+        for warning in &prepared.warnings {
+            observer.log(&format!("Warning: {}", warning));
+        }
+        if let Some(ref validation) = prepared.rejected_dir {
+            observer.dir_invalid(validation);
+        }
+
+        let f_path = prepared.f_path.clone();
+        let ytdlp_path = prepared.ytdlp_path.clone();
+
+        // Refuse downloads that won't fit, then watch free space while yt-dlp runs
+        let threshold = config.low_disk_space_bytes();
+        let monitor = if threshold > 0 {
+            // Probing a playlist means a request per entry before anything downloads
+            if config.check_download_size && prepared.playlist {
+                observer.log("Skipping the download size check for playlists");
+            } else if config.check_download_size {
+                observer.log("Checking download size...");
+                let (path, args, url) = (ytdlp_path.clone(), prepared.args.clone(), prepared.url.clone());
+                let required = tauri::async_runtime::spawn_blocking(move || estimate_download_size(&path, &args, &url))
+                    .await
+                    .ok()
+                    .flatten();
+                let free = Storage::available_space(Path::new(&f_path));
+
+                if let (Some(required), Some(free)) = (required, free) {
+                    if free < required.saturating_add(threshold) {
+                        let message = format!(
+                            "Not enough disk space: download needs about {}, only {} free in {}",
+                            Storage::format_bytes(required),
+                            Storage::format_bytes(free),
+                            f_path
+                        );
+                        let disk_low = DiskLow {
+                            path: f_path.clone(),
+                            free_bytes: free,
+                            threshold_bytes: threshold,
+                            required_bytes: Some(required),
+                            message: message.clone(),
+                        };
+                        observer.disk_low(&disk_low);
+                        observer.error(&message);
+                        return Err(DownloadFailure { disk_low: Some(disk_low), ..message.into() });
+                    }
+                }
+            }
+
+            let observer = observer.clone();
+            let dir = f_path.clone();
+            Some(DiskMonitor::start(PathBuf::from(&f_path), threshold, move |free| {
+                let message = format!(
+                    "Disk space low: only {} free in {}, download stopped",
+                    Storage::format_bytes(free),
+                    dir
+                );
+                observer.disk_low(&DiskLow {
+                    path: dir,
+                    free_bytes: free,
+                    threshold_bytes: threshold,
+                    required_bytes: None,
+                    message,
+                });
+                let _ = process.terminate();
+            }))
+        } else {
+            None
+        };
+
+        if observer.cancelled() {
+            return Err("Download cancelled".to_string().into());
+        }
+
+        // yt-dlp describes each finished video for the history
+        let started_at = history::now_secs();
+        let args = prepared.run_args();
+
+        let mut run = run_ytdlp(observer, process, &ytdlp_path, &args, new_process_group)?;
+        let mut files = prepared.record_history(history, started_at, observer);
+
+        if monitor.as_ref().is_some_and(DiskMonitor::tripped) {
+            let message = "Download stopped because disk space is low".to_string();
+            observer.error(&message);
+            let disk_low = DiskLow {
+                free_bytes: Storage::available_space(Path::new(&f_path)).unwrap_or(0),
+                path: f_path.clone(),
+                threshold_bytes: threshold,
+                required_bytes: None,
+                message: message.clone(),
+            };
+            return Err(DownloadFailure { disk_low: Some(disk_low), ..message.into() });
+        }
+
+        // Extractor breakage is almost always fixed by a newer yt-dlp
+        let mut update_suggested = false;
+        if run.code != 0 && is_extractor_failure(&run.stderr) {
+            let pinned = config.binary_sources.ytdlp_version.as_deref().is_some_and(|v| !v.trim().is_empty());
+
+            if status.yt_dlp_source != Some(BinarySource::Bundled) {
+                observer.log("yt-dlp failed to extract this site. Updating your installed yt-dlp will likely fix it.");
+            } else if pinned {
+                observer.log("yt-dlp failed to extract this site, but it is pinned to a release in Settings and won't be updated.");
+            } else {
+                match (config.ytdlp_update_policy, app_handle) {
+                    (YtDlpUpdatePolicy::Never, _) => {}
+                    (YtDlpUpdatePolicy::Ask, _) | (YtDlpUpdatePolicy::Auto, None) => update_suggested = true,
+                    (YtDlpUpdatePolicy::Auto, Some(app_handle)) => {
+                        observer.log("yt-dlp failed to extract this site, updating yt-dlp and retrying...");
+                        let update = BinaryManager::download_ytdlp(app_handle).await;
+                        app_handle.state::<BinaryStatusCache>().invalidate();
+                        if let Err(e) = update {
+                            observer.error(&format!("yt-dlp update failed: {}", e));
+                            return Err(format!("yt-dlp update failed: {}", e).into());
+                        }
+                        run = run_ytdlp(observer, process, &ytdlp_path, &args, new_process_group)?;
+                        files.extend(prepared.record_history(history, started_at, observer));
+                    }
+                }
+            }
+        }
+
+        let code = run.code;
+
+        if code == 0 {
+            // Clear macOS quarantine attribute from downloaded files
+            #[cfg(target_os = "macos")]
+            if let Err(e) = clear_quarantine_attr(&f_path) {
+                eprintln!("Warning: Failed to clear quarantine attribute: {}", e);
+            }
+
+            Ok(DownloadResult { code, files })
+        } else {
+            let message = format!("yt-dlp exited with code {}", code);
+            observer.error(&message);
+            Err(DownloadFailure { message, code: Some(code), update_suggested, disk_low: None })
+        }
+    }
+}
+
+/// Whether yt-dlp may be given the URL: http(s) with a host, so it can't be read as an option
+pub fn is_web_url(url: &str) -> bool {
+    url::Url::parse(url)
+        .is_ok_and(|parsed| matches!(parsed.scheme(), "http" | "https") && parsed.host_str().is_some())
+}
+
+/// Entries probed at most, in case a single-video download turns out to be a playlist
+const MAX_SIZE_PROBE_ENTRIES: u32 = 10;
+
+/// Asks yt-dlp for the expected size of a download, summed over playlist entries
+pub fn estimate_download_size(ytdlp_path: &str, args: &[String], url: &str) -> Option<u64> {
+    use std::process::Command;
+
+    // --print implies --simulate, so nothing is downloaded
+    let output = Command::new(ytdlp_path)
+        .args(args)
+        .args(["--playlist-end", &MAX_SIZE_PROBE_ENTRIES.to_string()])
+        .args(["--print", "%(filesize,filesize_approx)s", "--", url])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let sizes: Vec<u64> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().parse::<f64>().ok())
+        .map(|size| size as u64)
+        .collect();
+
+    if sizes.is_empty() {
+        None
+    } else {
+        Some(sizes.iter().sum())
+    }
+}
+
+/// Outcome of a single yt-dlp invocation
+pub struct YtDlpRun {
+    pub code: i32,
+    pub stderr: Vec<String>,
+}
+
+/// Runs yt-dlp to completion, forwarding output to the observer. `new_process_group`
+/// lets `ProcessSlot::terminate` kill ffmpeg children too; the command line
+/// leaves it off so Ctrl-C in the terminal reaches yt-dlp.
+pub fn run_ytdlp<O: DownloadObserver>(
+    observer: &O,
+    process: &ProcessSlot,
+    ytdlp_path: &str,
+    args: &[String],
+    new_process_group: bool,
+) -> Result<YtDlpRun, String> {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    // Create process group on Unix to ensure child processes (ffmpeg) can be killed
+    #[cfg(unix)]
+    let mut child = {
+        use std::os::unix::process::CommandExt;
+        let mut command = Command::new(ytdlp_path);
+        command.args(args).stdout(Stdio::piped()).stderr(Stdio::piped());
+        if new_process_group {
+            command.process_group(0); // Create new process group
+        }
+        command.spawn().map_err(|e| format!("Failed to spawn yt-dlp: {}", e))?
+    };
+
+    #[cfg(not(unix))]
+    let _ = new_process_group;
+    #[cfg(not(unix))]
+    let mut child = Command::new(ytdlp_path)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn yt-dlp: {}", e))?;
+
+    // Store process ID for cancellation
+    process.set(child.id());
+
+    observer.progress(0);
+
+    // Drain stderr concurrently so a full pipe can't stall yt-dlp, keeping lines for error detection
+    let stderr_reader = child.stderr.take().map(|stderr| {
+        let observer = observer.clone();
+        std::thread::spawn(move || {
+            let err_reader = BufReader::new(stderr);
+            let mut lines = Vec::new();
+            for l in err_reader.lines().map_while(Result::ok) {
+                observer.log(&l);
+                lines.push(l);
+            }
+            lines
+        })
+    });
+
+    // Read stdout for progress
+    let stdout = child.stdout.take().unwrap();
+    let mut reader = BufReader::new(stdout);
+
+    let mut last_percent: u8 = 0; 
+    let mut line = String::new();
+
+    while reader.read_line(&mut line).map_err(|e| e.to_string())? > 0 {
+        let l = line.trim_end().to_string();
+        observer.log(&l);
+
+        if l.contains("[download]") && l.contains("Destination:") {
+            last_percent = 0;
+        }
+
+        if let Some(p) = parse_progress_percent(&l) {
+            let p = p.min(100);
+            if p > last_percent {
+                last_percent = p;
+                observer.progress(p);
+            }
+        }
+
+        line.clear();
+}
+
+    let status = child.wait().map_err(|e| format!("wait failed: {}", e))?;
+
+    // Clear the process ID
+    process.clear();
+
+    let stderr = stderr_reader
+        .map(|reader| reader.join().unwrap_or_default())
+        .unwrap_or_default();
+
+    Ok(YtDlpRun {
+        code: status.code().unwrap_or(-1),
+        stderr,
+    })
+}
+
+/// Clears macOS Gatekeeper quarantine attribute from files in directory
+#[cfg(target_os = "macos")]
+pub fn clear_quarantine_attr(dir_path: &str) -> Result<(), String> {
+    use std::process::Command;
+    use std::path::Path;
+
+    let path = Path::new(dir_path);
+    if !path.exists() {
+        return Err(format!("Path does not exist: {}", dir_path));
+    }
+
+    // Run: xattr -dr com.apple.quarantine <path>
+    let output = Command::new("xattr")
+        .args(["-dr", "com.apple.quarantine", dir_path])
+        .output()
+        .map_err(|e| format!("Failed to execute xattr: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // Non-zero exit is OK if attribute doesn't exist
+        if !stderr.contains("No such xattr") {
+            return Err(format!("xattr failed: {}", stderr));
+        }
+    }
+
+    Ok(())
+}
+
+/// Detects yt-dlp errors caused by site changes that a yt-dlp update usually fixes
+pub fn is_extractor_failure(stderr: &[String]) -> bool {
+    const PATTERNS: [&str; 5] = [
+        "Unable to extract",
+        "nsig extraction failed",
+        "Signature extraction failed",
+        "Failed to extract any player response",
+        "Confirm you are on the latest version",
+    ];
+
+    stderr
+        .iter()
+        .any(|line| PATTERNS.iter().any(|pattern| line.contains(pattern)))
+}
+
+pub fn parse_progress_percent(line: &str) -> Option<u8> {
+    if line.contains("[download]") && line.contains('%') {
+        let start = line.find(']').unwrap_or(0) + 1;
+        let slice = &line[start..];
+        for word in slice.split_whitespace() {
+            if let Some(stripped) = word.strip_suffix('%') {
+                if let Ok(p) = stripped.parse::<f32>() {
+                    // use floor to reduce flicker
+                    return Some(p.floor() as u8);
+                }
+            }
+        }
+    }
+    None
+}
+
+impl Default for ProcessSlot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessSlot {
+    pub const fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    fn set(&self, pid: u32) {
+        *self.0.lock().unwrap() = Some(Arc::new(Mutex::new(Some(pid))));
+    }
+
+    fn clear(&self) {
+        *self.0.lock().unwrap() = None;
+    }
+
+    /// Kills the running yt-dlp process tree, if any
+    pub fn terminate(&self) -> Result<(), String> {
+        let current = self.0.lock().unwrap();
+        if let Some(ref pid_arc) = *current {
+            let mut pid_lock = pid_arc.lock().unwrap();
+            if let Some(pid) = *pid_lock {
+                #[cfg(unix)]
+                {
+                    // Kill the entire process group (negative PID) to terminate ffmpeg children
+                    let result = unsafe {
+                        libc::kill(-(pid as i32), libc::SIGTERM)
+                    };
+
+                    if result != 0 {
+                        let err = std::io::Error::last_os_error();
+                        // ESRCH (No such process) is acceptable - process already terminated
+                        if err.raw_os_error() != Some(libc::ESRCH) {
+                            return Err(format!("Failed to terminate process group: {}", err));
+                        }
+                    } else {
+                        // Grace period: escalate to SIGKILL if process doesn't terminate
+                        // Share the Arc to verify PID hasn't been reused before SIGKILL
+                        let pid_arc_clone = Arc::clone(pid_arc);
+                        std::thread::spawn(move || {
+                            std::thread::sleep(std::time::Duration::from_secs(2));
+                            // Verify PID still matches to prevent killing wrong process
+                            if let Ok(guard) = pid_arc_clone.lock() {
+                                if *guard == Some(pid) {
+                                    unsafe {
+                                        // Attempt SIGKILL - ignore errors (process may have exited)
+                                        libc::kill(-(pid as i32), libc::SIGKILL);
+                                    }
+                                }
+                            }
+                        });
+                    }
+                }
+                #[cfg(windows)]
+                {
+                    use std::process::Command;
+                    // Use /T flag to terminate the process tree including ffmpeg
+                    let result = Command::new("taskkill")
+                        .args(&["/PID", &pid.to_string(), "/F", "/T"])
+                        .output();
+
+                    if let Err(e) = result {
+                        return Err(format!("Failed to execute taskkill: {}", e));
+                    }
+                }
+
+                // Clear PID atomically after kill attempt
+                *pid_lock = None;
+                return Ok(());
+            }
+        }
+        Err("No active download to cancel".to_string())
+    }
+}
+
+/// Kills the app's running download, if any
+pub fn terminate_current_download() -> Result<(), String> {
+    CURRENT_PROCESS.terminate()
+}
@@ -0,0 +1,228 @@
+use std::time::Duration;
+use crate::downloader;
+
+const YOUTUBE_NS: &str = "http://www.youtube.com/xml/schemas/2015";
+
+/// A downloadable item of an RSS or Atom feed
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeedItem {
+    /// guid / Atom id, the link when the feed has neither
+    pub id: String,
+    pub title: Option<String>,
+    /// Podcast enclosure, otherwise the item's link
+    pub url: String,
+    /// Set for YouTube channel feeds, matching download archive entries
+    pub video_id: Option<String>,
+}
+
+/// Result of fetching a feed
+pub enum FeedFetch {
+    /// The server answered 304 to our validators
+    NotModified,
+    Updated {
+        items: Vec<FeedItem>,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+}
+
+pub struct FeedPoller;
+
+impl FeedPoller {
+    /// Fetch a feed over HTTP(S), sending validators from the previous fetch.
+    /// `file://` URLs are read from disk, so polling can run against local fixtures.
+    pub fn fetch(url: &str, etag: Option<&str>, last_modified: Option<&str>) -> Result<FeedFetch, String> {
+        let parsed = url::Url::parse(url).map_err(|_| format!("Invalid feed URL: {}", url))?;
+
+        if parsed.scheme() == "file" {
+            let path = parsed.to_file_path().map_err(|_| format!("Invalid feed path: {}", url))?;
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read feed {}: {}", path.display(), e))?;
+            return Ok(FeedFetch::Updated {
+                items: Self::parse(&content)?,
+                etag: None,
+                last_modified: None,
+            });
+        }
+
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent("ezdl")
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let mut request = client.get(url);
+        if let Some(etag) = etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send().map_err(|e| format!("Failed to fetch feed: {}", e))?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(FeedFetch::NotModified);
+        }
+        let response = response.error_for_status().map_err(|e| format!("Failed to fetch feed: {}", e))?;
+
+        let header = |name| {
+            response.headers()
+                .get(name)
+                .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(reqwest::header::ETAG);
+        let last_modified = header(reqwest::header::LAST_MODIFIED);
+
+        let body = response.text().map_err(|e| format!("Failed to read feed: {}", e))?;
+        Ok(FeedFetch::Updated {
+            items: Self::parse(&body)?,
+            etag,
+            last_modified,
+        })
+    }
+
+    /// Items of an RSS 2.0, RSS 1.0 or Atom document, in document order
+    pub fn parse(xml: &str) -> Result<Vec<FeedItem>, String> {
+        // Old RSS 0.91 feeds still carry a DOCTYPE
+        let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+        let doc = roxmltree::Document::parse_with_options(xml, options)
+            .map_err(|e| format!("Invalid feed: {}", e))?;
+
+        let root = doc.root_element();
+        if !matches!(root.tag_name().name(), "rss" | "feed" | "RDF") {
+            return Err(format!("Not an RSS or Atom feed (root element <{}>)", root.tag_name().name()));
+        }
+
+        Ok(root
+            .descendants()
+            .filter(|node| node.is_element() && matches!(node.tag_name().name(), "item" | "entry"))
+            .filter_map(Self::parse_item)
+            .collect())
+    }
+
+    fn parse_item(node: roxmltree::Node) -> Option<FeedItem> {
+        let child_text = |name: &str| {
+            node.children()
+                .find(|child| child.is_element() && child.tag_name().name() == name)
+                .and_then(|child| child.text())
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .map(str::to_string)
+        };
+
+        // Atom links carry href and rel, RSS links are plain text
+        let mut link = None;
+        let mut enclosure = None;
+        for child in node.children().filter(|child| child.is_element() && child.tag_name().name() == "link") {
+            match (child.attribute("href"), child.attribute("rel")) {
+                (Some(href), Some("enclosure")) => {
+                    enclosure.get_or_insert_with(|| href.to_string());
+                }
+                (Some(href), None | Some("alternate")) => {
+                    link.get_or_insert_with(|| href.to_string());
+                }
+                (None, _) => {
+                    if let Some(text) = child.text().map(str::trim).filter(|text| !text.is_empty()) {
+                        link.get_or_insert_with(|| text.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Podcasts put the audio file in <enclosure url="...">
+        if enclosure.is_none() {
+            enclosure = node.children()
+                .find(|child| child.is_element() && child.tag_name().name() == "enclosure")
+                .and_then(|child| child.attribute("url"))
+                .map(str::to_string);
+        }
+
+        let video_id = node.children()
+            .find(|child| child.is_element() && child.has_tag_name((YOUTUBE_NS, "videoId")))
+            .and_then(|child| child.text())
+            .map(|text| text.trim().to_string());
+
+        // The URL ends up on yt-dlp's command line, anything else is not ours to download
+        let url = enclosure.or(link).filter(|url| downloader::is_web_url(url))?;
+        let id = child_text("guid")
+            .or_else(|| child_text("id"))
+            .or_else(|| node.attribute(("http://www.w3.org/1999/02/22-rdf-syntax-ns#", "about")).map(str::to_string))
+            .unwrap_or_else(|| url.clone());
+
+        Some(FeedItem {
+            id,
+            title: child_text("title"),
+            url,
+            video_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Vec<FeedItem> {
+        let path = format!("{}/tests/fixtures/feeds/{}", env!("CARGO_MANIFEST_DIR"), name);
+        let xml = std::fs::read_to_string(&path).unwrap();
+        FeedPoller::parse(&xml).unwrap()
+    }
+
+    #[test]
+    fn rss2_prefers_enclosure_and_drops_non_web_links() {
+        let items = fixture("podcast-rss2.xml");
+        assert_eq!(items, vec![
+            FeedItem {
+                id: "episode-2".to_string(),
+                title: Some("Episode 2".to_string()),
+                url: "https://cdn.example.com/episode-2.mp3".to_string(),
+                video_id: None,
+            },
+            FeedItem {
+                id: "https://podcast.example.com/episodes/1".to_string(),
+                title: Some("Episode 1".to_string()),
+                url: "https://podcast.example.com/episodes/1".to_string(),
+                video_id: None,
+            },
+        ]);
+    }
+
+    #[test]
+    fn atom_uses_enclosure_then_alternate_link() {
+        let items = fixture("blog-atom.xml");
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].id, "urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a");
+        assert_eq!(items[0].url, "https://cdn.example.com/launch.mp4");
+        assert_eq!(items[1].title.as_deref(), Some("Behind the scenes"));
+        assert_eq!(items[1].url, "https://videos.example.com/behind-the-scenes");
+    }
+
+    #[test]
+    fn youtube_channel_reads_video_id() {
+        let items = fixture("youtube-channel.xml");
+        assert_eq!(items, vec![FeedItem {
+            id: "yt:video:dQw4w9WgXcQ".to_string(),
+            title: Some("Never Gonna Give You Up".to_string()),
+            url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+            video_id: Some("dQw4w9WgXcQ".to_string()),
+        }]);
+    }
+
+    #[test]
+    fn rss1_uses_rdf_about_as_id() {
+        let items = fixture("news-rss1.xml");
+        assert_eq!(items, vec![FeedItem {
+            id: "https://news.example.com/clips/42".to_string(),
+            title: Some("Clip 42".to_string()),
+            url: "https://news.example.com/clips/42".to_string(),
+            video_id: None,
+        }]);
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(FeedPoller::parse("<html><body/></html>").is_err());
+    }
+}
@@ -0,0 +1,352 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use fs2::FileExt;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use crate::config::write_atomic;
+use crate::paths::AppPaths;
+
+/// yt-dlp `--print-to-file` template written once per finished video
+const RECORD_TEMPLATE: &str = "after_move:%(.{id,title,extractor,webpage_url,duration,filepath})j";
+
+/// One downloaded video, stored as a line of `history.jsonl`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// URL as it was queued, a playlist URL for playlist entries
+    pub url: String,
+    pub webpage_url: Option<String>,
+    pub extractor: Option<String>,
+    pub video_id: Option<String>,
+    pub title: Option<String>,
+    pub preset: Option<String>,
+    pub files: Vec<String>,
+    pub size_bytes: Option<u64>,
+    pub duration_secs: Option<f64>,
+    /// Unix timestamps in seconds
+    pub started_at: u64,
+    pub finished_at: u64,
+}
+
+/// Filters for `get_history`. Unset fields match everything.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// Case-insensitive text searched in titles and URLs
+    pub search: Option<String>,
+    /// Extractor name ("youtube") or host ("youtube.com")
+    pub site: Option<String>,
+    /// Only entries finished at or after this Unix timestamp
+    pub from: Option<u64>,
+    /// Only entries finished before this Unix timestamp
+    pub to: Option<u64>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+impl HistoryQuery {
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        if self.from.is_some_and(|from| entry.finished_at < from) {
+            return false;
+        }
+        if self.to.is_some_and(|to| entry.finished_at >= to) {
+            return false;
+        }
+
+        if let Some(site) = self.site.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            let site = site.to_lowercase();
+            let extractor_matches = entry.extractor.as_deref()
+                .is_some_and(|extractor| extractor.to_lowercase() == site);
+            let host_matches = [Some(&entry.url), entry.webpage_url.as_ref()]
+                .into_iter()
+                .flatten()
+                .filter_map(|url| url::Url::parse(url).ok())
+                .filter_map(|url| url.host_str().map(str::to_lowercase))
+                .any(|host| host == site || host.ends_with(&format!(".{}", site)));
+            if !extractor_matches && !host_matches {
+                return false;
+            }
+        }
+
+        if let Some(search) = self.search.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            let search = search.to_lowercase();
+            let found = [entry.title.as_ref(), Some(&entry.url), entry.webpage_url.as_ref()]
+                .into_iter()
+                .flatten()
+                .any(|text| text.to_lowercase().contains(&search));
+            if !found {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// A finished video as yt-dlp describes it through `RECORD_TEMPLATE`
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct RecordedVideo {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub extractor: Option<String>,
+    pub webpage_url: Option<String>,
+    pub duration: Option<f64>,
+    /// Final path, after merging, conversion and moving
+    pub filepath: Option<String>,
+}
+
+pub fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// A line of `history.jsonl`. Rewrites keep lines verbatim, so entries this
+/// version can't parse (damaged, or from a newer version) aren't lost.
+struct StoredLine {
+    text: String,
+    entry: Option<HistoryEntry>,
+}
+
+/// Held while the history is read or changed. The file lock keeps the command
+/// line and the app from interleaving rewrites.
+struct HistoryLock<'a> {
+    _guard: MutexGuard<'a, ()>,
+    _file: fs::File,
+}
+
+/// Download history in `app_data_dir/history.jsonl`, newest entries last
+pub struct HistoryStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl HistoryStore {
+    pub fn new(paths: &AppPaths) -> Self {
+        Self {
+            path: paths.data_dir.join("history.jsonl"),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// yt-dlp arguments that make it describe each finished video in `record_file`
+    pub fn ytdlp_args(record_file: &Path) -> Vec<String> {
+        vec![
+            "--print-to-file".to_string(),
+            RECORD_TEMPLATE.to_string(),
+            record_file.to_string_lossy().into_owned(),
+        ]
+    }
+
+    /// Scratch file for one download's `ytdlp_args` output
+    pub fn record_file() -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        std::env::temp_dir().join(format!("ezdl-record-{}-{}.jsonl", std::process::id(), nanos))
+    }
+
+    // The history file is replaced on rewrites, so the lock is taken on a file beside it
+    fn lock(&self) -> Result<HistoryLock<'_>, String> {
+        let guard = self.lock.lock().unwrap();
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("lock"))
+            .map_err(|e| format!("Failed to open history lock: {}", e))?;
+        file.lock_exclusive().map_err(|e| format!("Failed to lock history: {}", e))?;
+        Ok(HistoryLock { _guard: guard, _file: file })
+    }
+
+    // Counter beside the history, so ids stay unique after the newest entries are deleted
+    fn next_id_path(&self) -> PathBuf {
+        self.path.with_extension("next-id")
+    }
+
+    fn read_next_id(&self) -> u64 {
+        fs::read_to_string(self.next_id_path())
+            .ok()
+            .and_then(|content| content.trim().parse().ok())
+            .unwrap_or(1)
+    }
+
+    // Every non-empty line, failing on read errors rather than returning part of the file
+    fn read_lines(&self) -> Result<Vec<StoredLine>, String> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Failed to read history: {}", e)),
+        };
+
+        let mut lines = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let text = line.map_err(|e| format!("Failed to read history: {}", e))?;
+            if text.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&text).ok();
+            lines.push(StoredLine { text, entry });
+        }
+        Ok(lines)
+    }
+
+    // Every stored entry, skipping lines that don't parse
+    fn read_all(&self) -> Result<Vec<HistoryEntry>, String> {
+        Ok(self.read_lines()?.into_iter().filter_map(|line| line.entry).collect())
+    }
+
+    fn write_lines(&self, lines: &[StoredLine]) -> Result<(), String> {
+        let mut content = String::new();
+        for line in lines {
+            content.push_str(&line.text);
+            content.push('\n');
+        }
+        write_atomic(&self.path, content.as_bytes())
+            .map_err(|e| format!("Failed to write history: {}", e))
+    }
+
+    // Remove the entries `remove` picks, returning how many were removed
+    fn delete_where(&self, remove: impl Fn(&HistoryEntry) -> bool) -> Result<usize, String> {
+        let _lock = self.lock()?;
+        let mut lines = self.read_lines()?;
+        let before = lines.len();
+        lines.retain(|line| !line.entry.as_ref().is_some_and(&remove));
+
+        let removed = before - lines.len();
+        if removed > 0 {
+            self.write_lines(&lines)?;
+        }
+        Ok(removed)
+    }
+
+    /// Videos yt-dlp reported in `record_file` since the last read. The file is removed.
+    pub fn read_record(record_file: &Path) -> Result<Vec<RecordedVideo>, String> {
+        let content = match fs::read_to_string(record_file) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Failed to read download record: {}", e)),
+        };
+        let _ = fs::remove_file(record_file);
+
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Store finished videos of a download
+    pub fn add(
+        &self,
+        videos: &[RecordedVideo],
+        url: &str,
+        preset: Option<&str>,
+        started_at: u64,
+    ) -> Result<Vec<HistoryEntry>, String> {
+        if videos.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let _lock = self.lock()?;
+        // Ids of deleted entries are never handed out again, the UI and API may still refer to them
+        let stored_next_id = self.read_all()?.iter().map(|entry| entry.id).max().map_or(1, |id| id + 1);
+        let mut next_id = self.read_next_id().max(stored_next_id);
+        let finished_at = now_secs();
+
+        let entries: Vec<HistoryEntry> = videos
+            .iter()
+            .cloned()
+            .map(|video| {
+                let files: Vec<String> = video.filepath.into_iter().collect();
+                let size_bytes = files.iter()
+                    .filter_map(|file| fs::metadata(file).ok())
+                    .map(|metadata| metadata.len())
+                    .reduce(|a, b| a + b);
+                let entry = HistoryEntry {
+                    id: next_id,
+                    url: url.to_string(),
+                    webpage_url: video.webpage_url,
+                    extractor: video.extractor,
+                    video_id: video.id,
+                    title: video.title,
+                    preset: preset.map(str::to_string),
+                    files,
+                    size_bytes,
+                    duration_secs: video.duration,
+                    started_at,
+                    finished_at,
+                };
+                next_id += 1;
+                entry
+            })
+            .collect();
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open history: {}", e))?;
+        for entry in &entries {
+            let line = serde_json::to_string(entry)
+                .map_err(|e| format!("Failed to serialize history: {}", e))?;
+            writeln!(file, "{}", line).map_err(|e| format!("Failed to write history: {}", e))?;
+        }
+        write_atomic(&self.next_id_path(), next_id.to_string().as_bytes())
+            .map_err(|e| format!("Failed to write history: {}", e))?;
+
+        Ok(entries)
+    }
+
+    /// Whether a download recorded this file, so it is safe to open
+    pub fn contains_file(&self, path: &str) -> Result<bool, String> {
+        let _lock = self.lock()?;
+        Ok(self.read_all()?.iter().any(|entry| entry.files.iter().any(|file| file == path)))
+    }
+
+    /// Whether a URL was downloaded before, as queued or as the video's page
+    pub fn contains_url(&self, url: &str) -> Result<bool, String> {
+        Ok(self.downloaded_urls()?.contains(url.trim().trim_end_matches('/')))
+    }
+
+    /// Queued and page URLs of every entry, without trailing slashes
+    pub fn downloaded_urls(&self) -> Result<HashSet<String>, String> {
+        let _lock = self.lock()?;
+        Ok(self.read_all()?
+            .into_iter()
+            .flat_map(|entry| std::iter::once(entry.url).chain(entry.webpage_url))
+            .map(|url| url.trim_end_matches('/').to_string())
+            .collect())
+    }
+
+    /// Matching entries, newest first
+    pub fn query(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>, String> {
+        let _lock = self.lock()?;
+        let entries = self.read_all()?;
+
+        Ok(entries
+            .into_iter()
+            .rev()
+            .filter(|entry| query.matches(entry))
+            .skip(query.offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Delete entries by id, returning how many were removed. Downloaded files are kept.
+    pub fn delete(&self, ids: &[u64]) -> Result<usize, String> {
+        self.delete_where(|entry| ids.contains(&entry.id))
+    }
+
+    /// Delete every entry matching the query, returning how many were removed
+    pub fn delete_matching(&self, query: &HistoryQuery) -> Result<usize, String> {
+        self.delete_where(|entry| query.matches(entry))
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::Mutex;
+use tauri::Manager;
+use crate::deep_link;
+use crate::queue::{DownloadQueue, QueuedDownload};
+
+/// Downloads given on the command line when opening the app, e.g. from a file
+/// manager or `ezdl URL... [--preset NAME] [--dir DIR]`
+#[derive(Default, Debug)]
+pub struct LaunchArgs {
+    pub urls: Vec<String>,
+    pub preset: Option<String>,
+    pub download_dir: Option<String>,
+}
+
+impl LaunchArgs {
+    /// Parse arguments without the program name. Returns what was understood and
+    /// a warning for everything else.
+    pub fn parse(args: &[String]) -> (Self, Vec<String>) {
+        let mut launch = LaunchArgs::default();
+        let mut warnings = Vec::new();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--preset" | "--dir" => match args.next() {
+                    Some(value) if arg == "--preset" => launch.preset = Some(value.clone()),
+                    Some(value) => launch.download_dir = Some(value.clone()),
+                    None => warnings.push(format!("{} needs a value", arg)),
+                },
+                _ if Self::is_download_url(arg) => launch.urls.push(arg.clone()),
+                // Handled by the deep-link plugin
+                _ if arg.starts_with(&format!("{}:", deep_link::SCHEME)) => {}
+                _ => warnings.push(format!("Ignoring argument {}", arg)),
+            }
+        }
+        (launch, warnings)
+    }
+
+    fn is_download_url(arg: &str) -> bool {
+        url::Url::parse(arg).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+    }
+
+    pub fn downloads(&self) -> Vec<QueuedDownload> {
+        self.urls
+            .iter()
+            .map(|url| QueuedDownload {
+                url: url.clone(),
+                title: None,
+                preset: self.preset.clone(),
+                download_dir: self.download_dir.clone(),
+                source: "command line".to_string(),
+            })
+            .collect()
+    }
+}
+
+/// Holds launch downloads until the UI has loaded its queue and can take events
+pub struct LaunchQueue {
+    /// None once the UI has taken the pending downloads
+    pending: Mutex<Option<Vec<QueuedDownload>>>,
+}
+
+impl Default for LaunchQueue {
+    fn default() -> Self {
+        Self { pending: Mutex::new(Some(Vec::new())) }
+    }
+}
+
+impl LaunchQueue {
+    /// Queue the downloads of a launch, this process's own or one forwarded by a second instance
+    pub fn handle(app_handle: &tauri::AppHandle, args: &[String]) {
+        let (launch, warnings) = LaunchArgs::parse(args);
+        for warning in warnings {
+            eprintln!("Warning: {}", warning);
+        }
+
+        let items = launch.downloads();
+        if items.is_empty() {
+            return;
+        }
+
+        let state = app_handle.state::<LaunchQueue>();
+        let mut pending = state.pending.lock().unwrap();
+        match pending.as_mut() {
+            Some(pending) => pending.extend(items),
+            None => DownloadQueue::enqueue(app_handle, items, true),
+        }
+    }
+
+    /// Downloads that arrived before the UI was ready. Later ones are sent as `queue-add`.
+    pub fn take(&self) -> Vec<QueuedDownload> {
+        self.pending.lock().unwrap().take().unwrap_or_default()
+    }
+}
+
+/// Bring the window of the running instance to the front
+pub fn show_main_window(app_handle: &tauri::AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
@@ -3,31 +3,169 @@
     windows_subsystem = "windows"
 )]
 
+mod api;
+mod archive;
+mod cli;
 mod config;
+mod config_bundle;
+mod deep_link;
+mod downloader;
+mod feeds;
 mod binary_manager;
+mod clipboard;
+mod history;
+mod launch;
+mod paths;
+mod presets;
+mod queue;
+mod site_rules;
+mod storage;
+mod subscriptions;
+mod url_import;
+
+use api::LocalApi;
+use archive::{ArchiveEntry, DownloadArchive};
+use config::{ConfigChange, ConfigManager, ConfigState, UserConfig};
+use config_bundle::{ConfigBundle, ImportSummary};
+use deep_link::{DeepLinkRequest, DeepLinks};
+use downloader::{DownloadContext, DownloadFailure, DownloadRequest, DownloadResult, Downloader};
+use clipboard::ClipboardMonitor;
+use binary_manager::{BinaryManager, BinaryStatus, BinaryStatusCache};
+use history::{HistoryEntry, HistoryQuery, HistoryStore};
+use launch::LaunchQueue;
+use paths::AppPaths;
+use presets::{DownloadPreset, PresetManager, PresetOverrides};
+use queue::{DownloadQueue, QueuedDownload, SavedQueue, SavedQueueItem};
+use site_rules::{SiteRule, SiteRules};
+use storage::{DirValidation, Storage};
+use subscriptions::{Subscription, SubscriptionStore};
+use url_import::{ImportReport, UrlImport};
+
+use tauri::{Emitter, Listener, Manager, State, Window};
+use std::collections::BTreeMap;
 
-use config::{ConfigManager, UserConfig};
-use binary_manager::{BinaryManager, BinaryStatus};
-
-use tauri::Window;
-use std::sync::{Arc, Mutex};
-
-// Global state to track current download process
-static CURRENT_PROCESS: Mutex<Option<Arc<Mutex<Option<u32>>>>> = Mutex::new(None);
 fn main() {
+    // `ezdl get URL` and friends run headless and exit
+    let args: Vec<String> = std::env::args().collect();
+    if cli::is_command(&args) {
+        std::process::exit(cli::run(&args[1..]));
+    }
+
     tauri::Builder::default()
+        // Must come first: a second launch forwards its arguments here and exits
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            launch::show_main_window(app);
+            LaunchQueue::handle(app, args.get(1..).unwrap_or_default());
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_opener::init())  
+        .manage(BinaryStatusCache::default())
+        .manage(LaunchQueue::default())
+        .manage(DeepLinks::default())
+        .manage(LocalApi::default())
+        .setup(move |app| {
+            let paths = AppPaths::from_app(app.handle())?;
+            let config = match ConfigManager::load_config(&paths) {
+                Ok((config, warnings)) => {
+                    for warning in warnings {
+                        let _ = app.emit("config-warning", warning);
+                    }
+                    config
+                }
+                Err(e) => {
+                    eprintln!("Warning: {}, using default settings", e);
+                    UserConfig::new()
+                }
+            };
+            let api_enabled = config.api_enabled;
+            app.manage(ConfigState::new(config));
+            app.manage(HistoryStore::new(&paths));
+            app.manage(SubscriptionStore::load(&paths)?);
+            app.manage(paths);
+            app.state::<LocalApi>().apply(app.handle(), api_enabled);
+            SubscriptionStore::start(app.handle().clone());
+            ClipboardMonitor::start(app.handle().clone());
+            LaunchQueue::handle(app.handle(), args.get(1..).unwrap_or_default());
+
+            // ezdl:// links, from this launch and any later one
+            {
+                use tauri_plugin_deep_link::DeepLinkExt;
+
+                // Installers register the scheme, this covers AppImages and dev builds
+                #[cfg(any(target_os = "linux", windows))]
+                if let Err(e) = app.deep_link().register_all() {
+                    eprintln!("Warning: Failed to register {}:// links: {}", deep_link::SCHEME, e);
+                }
+
+                if let Ok(Some(links)) = app.deep_link().get_current() {
+                    DeepLinks::handle(app.handle(), links);
+                }
+                let handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    launch::show_main_window(&handle);
+                    DeepLinks::handle(&handle, event.urls());
+                });
+            }
+
+            if let Err(e) = app.state::<BinaryStatusCache>().watch(app.handle()) {
+                eprintln!("Warning: {}", e);
+            }
+
+            // Binary overrides, sources and use_system_binaries all affect resolution
+            let handle = app.handle().clone();
+            app.listen("config-changed", move |event| {
+                if let Ok(change) = serde_json::from_str::<ConfigChange>(event.payload()) {
+                    let affects_binaries = change.keys.iter()
+                        .any(|key| key.starts_with("binary_") || key == "use_system_binaries");
+                    if affects_binaries {
+                        handle.state::<BinaryStatusCache>().invalidate();
+                    }
+                    if change.keys.iter().any(|key| key == "api_enabled") {
+                        handle.state::<LocalApi>().apply(&handle, change.config.api_enabled);
+                    }
+                }
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             download_url,
             cancel_download,
             quit_app,
             get_config,
             update_config,
+            patch_config,
+            export_config,
+            import_config,
             get_download_dir,
+            validate_download_dir,
+            create_download_dir,
+            get_presets,
+            save_preset,
+            delete_preset,
+            rename_preset,
+            match_site_rule,
+            get_history,
+            delete_history,
+            clear_history,
+            get_download_archive,
+            remove_from_download_archive,
+            open_download,
+            get_subscriptions,
+            save_subscription,
+            delete_subscription,
+            check_subscription,
+            get_saved_queue,
+            save_queue,
+            take_launch_downloads,
+            import_urls,
+            get_pending_links,
+            resolve_link,
+            show_download_in_folder,
             fetch_video_title,
             check_binaries,
+            refresh_binaries,
             download_ytdlp,
             download_ffmpeg,
             download_all_binaries
@@ -73,304 +211,285 @@ fn quit_app(app_handle: tauri::AppHandle) {
 }
 
 #[tauri::command]
-fn get_config(app_handle: tauri::AppHandle) -> Result<UserConfig, String> {
-    ConfigManager::load_config(&app_handle)
+fn get_config(config: State<'_, ConfigState>) -> Result<UserConfig, String> {
+    Ok(config.get())
 }
 
 #[tauri::command]
-fn update_config(app_handle: tauri::AppHandle, new_config: UserConfig) -> Result<(), String> {
-    ConfigManager::save_config(&app_handle, &new_config)
+fn update_config(app_handle: tauri::AppHandle, config: State<'_, ConfigState>, new_config: UserConfig) -> Result<(), String> {
+    config.replace(&app_handle, new_config)?;
+    Ok(())
 }
 
 #[tauri::command]
-fn get_download_dir(app_handle: tauri::AppHandle) -> Result<String, String> {
-    ConfigManager::get_download_dir(&app_handle)
+fn patch_config(app_handle: tauri::AppHandle, config: State<'_, ConfigState>, patch: serde_json::Value) -> Result<UserConfig, String> {
+    config.patch(&app_handle, &patch)
 }
+
 #[tauri::command]
-async fn download_url(
-    app_handle: tauri::AppHandle,
-    window: Window,
-    url: String,
-    f_path: String,
-    mp3_only: bool,
-    enable_playlist: bool,
-    sponsorblock: bool,
-    cookies: bool
-) -> Result<(), String> {
-    use std::io::{BufRead, BufReader};
-    use std::process::{Command, Stdio};
-    use tauri::Emitter;
-
-    // Get path to yt-dlp binary (prefers bundled, falls back to system)
-    let config = ConfigManager::load_config(&app_handle)?;
-    let status = BinaryManager::check_binaries(&app_handle)?;
-
-    if !status.yt_dlp_installed {
-        if config.use_system_binaries {
-            return Err("yt-dlp not found. Please install yt-dlp from your package manager or enable binary downloads in Settings.".to_string());
-        } else {
-            return Err("Bundled binaries not found. Please download binaries from File menu > Download Binaries.".to_string());
-        }
-    }
+fn export_config(config: State<'_, ConfigState>, path: String, include_machine_paths: bool) -> Result<(), String> {
+    ConfigBundle::export(&config, std::path::Path::new(&path), include_machine_paths)
+}
 
-    // Warn about ffmpeg if needed for conversion
-    if mp3_only && !status.ffmpeg_installed {
-        if config.use_system_binaries {
-            return Err("ffmpeg not found. Audio conversion requires ffmpeg. Please install it from your package manager.".to_string());
-        } else {
-            return Err("ffmpeg not downloaded. Please download binaries from File menu > Download Binaries.".to_string());
-        }
-    }
+#[tauri::command]
+fn import_config(
+    app_handle: tauri::AppHandle,
+    config: State<'_, ConfigState>,
+    path: String,
+    include_machine_paths: bool,
+) -> Result<ImportSummary, String> {
+    ConfigBundle::import(&app_handle, &config, std::path::Path::new(&path), include_machine_paths)
+}
 
-    let ytdlp_path = status.yt_dlp_path.unwrap_or_else(|| "yt-dlp".to_string());
+#[tauri::command]
+fn get_presets(config: State<'_, ConfigState>) -> Result<BTreeMap<String, DownloadPreset>, String> {
+    Ok(config.get().presets)
+}
 
-    let output_template = format!("{}/%(title)s.%(ext)s", f_path);
+#[tauri::command]
+fn save_preset(app_handle: tauri::AppHandle, config: State<'_, ConfigState>, name: String, preset: DownloadPreset) -> Result<(), String> {
+    PresetManager::save(&app_handle, &config, &name, preset)
+}
 
-    let mut args = vec![
-        "--newline",
-        "-o", &output_template,
-        &url,
-    ];
+#[tauri::command]
+fn delete_preset(app_handle: tauri::AppHandle, config: State<'_, ConfigState>, name: String) -> Result<(), String> {
+    PresetManager::delete(&app_handle, &config, &name)
+}
 
-    // Only pass --ffmpeg-location if we have a bundled ffmpeg (not system PATH)
-    if let Some(ref ffmpeg_path) = status.ffmpeg_path {
-        if !ffmpeg_path.eq("ffmpeg") {
-            args.insert(1, "--ffmpeg-location");
-            args.insert(2, ffmpeg_path);
+#[tauri::command]
+fn rename_preset(app_handle: tauri::AppHandle, config: State<'_, ConfigState>, old_name: String, new_name: String) -> Result<(), String> {
+    // The archive moves first and back again if the config can't be saved,
+    // so the two never disagree about the preset's name
+    let paths = app_handle.state::<AppPaths>();
+    DownloadArchive::rename(&paths, old_name.trim(), new_name.trim())?;
+    if let Err(e) = PresetManager::rename(&app_handle, &config, &old_name, &new_name) {
+        if let Err(revert) = DownloadArchive::rename(&paths, new_name.trim(), old_name.trim()) {
+            eprintln!("Warning: {}", revert);
         }
+        return Err(e);
     }
+    Ok(())
+}
 
-    if mp3_only {
-        args.push("-x");
-        args.push("--audio-format");
-        args.push("mp3");
-    } else {
-        // Force merge to MKV for video downloads
-        args.push("--merge-output-format");
-        args.push("mkv");
-    }
-    if enable_playlist { args.push("--yes-playlist"); } else { args.push("--no-playlist"); }
-    if sponsorblock {
-        args.push("--sponsorblock-remove");
-        args.push("all");
-    }
-    if cookies {
-        args.push("--cookies-from-browser");
-        args.push("firefox");
-    }
-    // Create process group on Unix to ensure child processes (ffmpeg) can be killed
-    #[cfg(unix)]
-    let mut child = {
-        use std::os::unix::process::CommandExt;
-        Command::new(&ytdlp_path)
-            .args(&args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .process_group(0) // Create new process group
-            .spawn()
-            .map_err(|e| format!("Failed to spawn yt-dlp: {}", e))?
-    };
+#[tauri::command]
+fn match_site_rule(config: State<'_, ConfigState>, url: String) -> Result<Option<SiteRule>, String> {
+    let config = config.get();
+    Ok(SiteRules::find(&config.site_rules, &url)?.cloned())
+}
 
-    #[cfg(not(unix))]
-    let mut child = Command::new(&ytdlp_path)
-        .args(&args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to spawn yt-dlp: {}", e))?;
-
-    // Store process ID for cancellation
-    let pid = child.id();
-    {
-        let mut current = CURRENT_PROCESS.lock().unwrap();
-        *current = Some(Arc::new(Mutex::new(Some(pid))));
-    }
+#[tauri::command]
+fn get_history(history: State<'_, HistoryStore>, query: Option<HistoryQuery>) -> Result<Vec<HistoryEntry>, String> {
+    history.query(&query.unwrap_or_default())
+}
 
-    let _ = window.emit("download-progress", 0u8);
+#[tauri::command]
+fn delete_history(history: State<'_, HistoryStore>, ids: Vec<u64>) -> Result<usize, String> {
+    history.delete(&ids)
+}
 
-    // Read stdout for progress
-    let stdout = child.stdout.take().unwrap();
-    let mut reader = BufReader::new(stdout);
+// Delete everything matching the query, or the whole history without one
+#[tauri::command]
+fn clear_history(history: State<'_, HistoryStore>, query: Option<HistoryQuery>) -> Result<usize, String> {
+    history.delete_matching(&query.unwrap_or_default())
+}
 
-    let mut last_percent: u8 = 0; 
-    let mut line = String::new();
+#[tauri::command]
+fn get_subscriptions(subscriptions: State<'_, SubscriptionStore>) -> Result<Vec<Subscription>, String> {
+    Ok(subscriptions.list())
+}
 
-    while reader.read_line(&mut line).map_err(|e| e.to_string())? > 0 {
-        let l = line.trim_end().to_string();
-        let _ = window.emit("download-log", l.clone());
+#[tauri::command]
+fn save_subscription(
+    app_handle: tauri::AppHandle,
+    subscriptions: State<'_, SubscriptionStore>,
+    subscription: Subscription,
+) -> Result<Subscription, String> {
+    subscriptions.upsert(&app_handle, subscription)
+}
 
-        if l.contains("[download]") && l.contains("Destination:") {
-            last_percent = 0;
-        }
+#[tauri::command]
+fn delete_subscription(subscriptions: State<'_, SubscriptionStore>, id: u64) -> Result<(), String> {
+    subscriptions.delete(id)
+}
 
-        if let Some(p) = parse_progress_percent(&l) {
-            let p = p.min(100);
-            if p > last_percent {
-                last_percent = p;
-                let _ = window.emit("download-progress", p);
-            }
-        }
+// Check now, adding new videos to the queue without starting it
+#[tauri::command]
+async fn check_subscription(app_handle: tauri::AppHandle, id: u64) -> Result<Vec<QueuedDownload>, String> {
+    // Feed fetches use the blocking HTTP client, keep them off the async runtime
+    let handle = app_handle.clone();
+    let items = tauri::async_runtime::spawn_blocking(move || handle.state::<SubscriptionStore>().sync(&handle, id))
+        .await
+        .map_err(|e| format!("Subscription check failed: {}", e))??;
+    DownloadQueue::enqueue(&app_handle, items.clone(), false);
+    Ok(items)
+}
 
-        line.clear();
+#[tauri::command]
+fn get_saved_queue(paths: State<'_, AppPaths>) -> Result<Vec<SavedQueueItem>, String> {
+    SavedQueue::load(&paths)
 }
 
-    if let Some(stderr) = child.stderr.take() {
-        std::thread::spawn({
-            let window = window.clone();
-            move || {
-                let err_reader = BufReader::new(stderr);
-                for l in err_reader.lines().flatten() {
-                    let _ = window.emit("download-log", l);
-                }
-            }
-        });
-    }
+#[tauri::command]
+fn save_queue(paths: State<'_, AppPaths>, items: Vec<SavedQueueItem>) -> Result<(), String> {
+    SavedQueue::save(&paths, &items)
+}
 
-    let status = child.wait().map_err(|e| format!("wait failed: {}", e))?;
+// Add the links of a text, CSV or bookmarks file to the queue, skipping `queued` URLs
+#[tauri::command]
+fn import_urls(
+    app_handle: tauri::AppHandle,
+    config: State<'_, ConfigState>,
+    history: State<'_, HistoryStore>,
+    path: String,
+    queued: Vec<String>,
+) -> Result<ImportReport, String> {
+    let report = UrlImport::import(std::path::Path::new(&path), &config.get().presets, &history, &queued)?;
+    DownloadQueue::enqueue(&app_handle, report.items.clone(), false);
+    Ok(report)
+}
 
-    // Clear the process ID
-    {
-        let mut current = CURRENT_PROCESS.lock().unwrap();
-        *current = None;
-    }
+// Called once the UI has loaded its queue
+#[tauri::command]
+fn take_launch_downloads(launch: State<'_, LaunchQueue>) -> Vec<QueuedDownload> {
+    launch.take()
+}
 
-    let code = status.code().unwrap_or(-1);
+#[tauri::command]
+fn get_pending_links(links: State<'_, DeepLinks>) -> Vec<DeepLinkRequest> {
+    links.pending()
+}
 
-    if code == 0 {
-        // Clear macOS quarantine attribute from downloaded files
-        #[cfg(target_os = "macos")]
-        if let Err(e) = clear_quarantine_attr(&f_path) {
-            eprintln!("Warning: Failed to clear quarantine attribute: {}", e);
-        }
+// The user's answer to a `deep-link-confirm` prompt
+#[tauri::command]
+fn resolve_link(app_handle: tauri::AppHandle, links: State<'_, DeepLinks>, id: u64, accept: bool) -> Result<(), String> {
+    links.resolve(&app_handle, id, accept)
+}
 
-        let _ = window.emit("download-complete", code);
-        Ok(())
-    } else {
-        let _ = window.emit("download-error", format!("yt-dlp exited with code {}", code));
-        Err(format!("yt-dlp exited with code {}", code))
+// Only files a download produced may be opened from the UI
+fn check_downloaded_file(history: &HistoryStore, path: &str) -> Result<(), String> {
+    if !history.contains_file(path)? {
+        return Err(format!("Not a downloaded file: {}", path));
+    }
+    if !std::path::Path::new(path).exists() {
+        return Err(format!("File no longer exists: {}", path));
     }
+    Ok(())
 }
 
-/// Clears macOS Gatekeeper quarantine attribute from files in directory
-#[cfg(target_os = "macos")]
-fn clear_quarantine_attr(dir_path: &str) -> Result<(), String> {
-    use std::process::Command;
-    use std::path::Path;
+#[tauri::command]
+fn open_download(app_handle: tauri::AppHandle, history: State<'_, HistoryStore>, path: String) -> Result<(), String> {
+    use tauri_plugin_opener::OpenerExt;
 
-    let path = Path::new(dir_path);
-    if !path.exists() {
-        return Err(format!("Path does not exist: {}", dir_path));
-    }
+    check_downloaded_file(&history, &path)?;
+    app_handle.opener()
+        .open_path(&path, None::<&str>)
+        .map_err(|e| format!("Failed to open {}: {}", path, e))
+}
 
-    // Run: xattr -dr com.apple.quarantine <path>
-    let output = Command::new("xattr")
-        .args(["-dr", "com.apple.quarantine", dir_path])
-        .output()
-        .map_err(|e| format!("Failed to execute xattr: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        // Non-zero exit is OK if attribute doesn't exist
-        if !stderr.contains("No such xattr") {
-            return Err(format!("xattr failed: {}", stderr));
-        }
-    }
+#[tauri::command]
+fn show_download_in_folder(app_handle: tauri::AppHandle, history: State<'_, HistoryStore>, path: String) -> Result<(), String> {
+    use tauri_plugin_opener::OpenerExt;
 
-    Ok(())
+    check_downloaded_file(&history, &path)?;
+    app_handle.opener()
+        .reveal_item_in_dir(&path)
+        .map_err(|e| format!("Failed to show {}: {}", path, e))
 }
 
-fn parse_progress_percent(line: &str) -> Option<u8> {
-    if line.contains("[download]") && line.contains('%') {
-        let start = line.find(']').unwrap_or(0) + 1;
-        let slice = &line[start..];
-        for word in slice.split_whitespace() {
-            if let Some(stripped) = word.strip_suffix('%') {
-                if let Ok(p) = stripped.parse::<f32>() {
-                    // use floor to reduce flicker
-                    return Some(p.floor() as u8);
-                }
-            }
-        }
-    }
-    None
+#[tauri::command]
+fn get_download_archive(app_handle: tauri::AppHandle, preset: Option<String>) -> Result<Vec<ArchiveEntry>, String> {
+    DownloadArchive::entries(&app_handle.state::<AppPaths>(), preset.as_deref())
+}
+
+#[tauri::command]
+fn remove_from_download_archive(
+    app_handle: tauri::AppHandle,
+    preset: Option<String>,
+    entries: Vec<ArchiveEntry>,
+) -> Result<usize, String> {
+    DownloadArchive::remove(&app_handle.state::<AppPaths>(), preset.as_deref(), &entries)
 }
 
 #[tauri::command]
-fn check_binaries(app_handle: tauri::AppHandle) -> Result<BinaryStatus, String> {
-    BinaryManager::check_binaries(&app_handle)
+fn get_download_dir(app_handle: tauri::AppHandle) -> Result<String, String> {
+    ConfigManager::get_download_dir(&app_handle)
+}
+#[tauri::command]
+fn validate_download_dir(app_handle: tauri::AppHandle, config: State<'_, ConfigState>, path: Option<String>) -> Result<DirValidation, String> {
+    let config = config.get();
+    let dir = match path.or(config.download_dir.clone()) {
+        Some(dir) => dir,
+        None => ConfigManager::get_download_dir(&app_handle)?,
+    };
+    Ok(Storage::validate_dir(&dir, config.low_disk_space_bytes()))
 }
 
 #[tauri::command]
-async fn download_ytdlp(app_handle: tauri::AppHandle) -> Result<(), String> {
-    BinaryManager::download_ytdlp(&app_handle).await
+fn create_download_dir(config: State<'_, ConfigState>, path: Option<String>) -> Result<DirValidation, String> {
+    let config = config.get();
+    let dir = path.or(config.download_dir.clone())
+        .ok_or("No download folder configured")?;
+    Storage::create_dir(&dir)?;
+    Ok(Storage::validate_dir(&dir, config.low_disk_space_bytes()))
 }
 
 #[tauri::command]
-async fn download_ffmpeg(app_handle: tauri::AppHandle) -> Result<(), String> {
-    BinaryManager::download_ffmpeg(&app_handle).await
+async fn download_url(
+    app_handle: tauri::AppHandle,
+    window: Window,
+    url: String,
+    f_path: Option<String>,
+    preset: Option<String>,
+    overrides: Option<PresetOverrides>,
+) -> Result<DownloadResult, DownloadFailure> {
+    let request = DownloadRequest {
+        url,
+        f_path,
+        preset,
+        overrides: overrides.unwrap_or_default(),
+    };
+    let context = DownloadContext::app(&app_handle)?;
+    let result = Downloader::download(&context, &window, &downloader::CURRENT_PROCESS, request).await?;
+    let _ = window.emit("download-complete", &result);
+    Ok(result)
 }
 
 #[tauri::command]
-async fn download_all_binaries(app_handle: tauri::AppHandle) -> Result<(), String> {
-    BinaryManager::download_ytdlp(&app_handle).await?;
-    BinaryManager::download_ffmpeg(&app_handle).await?;
-    Ok(())
+fn check_binaries(app_handle: tauri::AppHandle, binaries: State<'_, BinaryStatusCache>) -> Result<BinaryStatus, String> {
+    binaries.get(&app_handle)
 }
 
 #[tauri::command]
-fn cancel_download() -> Result<(), String> {
-    let current = CURRENT_PROCESS.lock().unwrap();
-    if let Some(ref pid_arc) = *current {
-        let mut pid_lock = pid_arc.lock().unwrap();
-        if let Some(pid) = *pid_lock {
-            #[cfg(unix)]
-            {
-                // Kill the entire process group (negative PID) to terminate ffmpeg children
-                let result = unsafe {
-                    libc::kill(-(pid as i32), libc::SIGTERM)
-                };
-
-                if result != 0 {
-                    let err = std::io::Error::last_os_error();
-                    // ESRCH (No such process) is acceptable - process already terminated
-                    if err.raw_os_error() != Some(libc::ESRCH) {
-                        return Err(format!("Failed to terminate process group: {}", err));
-                    }
-                } else {
-                    // Grace period: escalate to SIGKILL if process doesn't terminate
-                    // Share the Arc to verify PID hasn't been reused before SIGKILL
-                    let pid_arc_clone = Arc::clone(pid_arc);
-                    std::thread::spawn(move || {
-                        std::thread::sleep(std::time::Duration::from_secs(2));
-                        // Verify PID still matches to prevent killing wrong process
-                        if let Ok(guard) = pid_arc_clone.lock() {
-                            if *guard == Some(pid) {
-                                unsafe {
-                                    // Attempt SIGKILL - ignore errors (process may have exited)
-                                    libc::kill(-(pid as i32), libc::SIGKILL);
-                                }
-                            }
-                        }
-                    });
-                }
-            }
-            #[cfg(windows)]
-            {
-                use std::process::Command;
-                // Use /T flag to terminate the process tree including ffmpeg
-                let result = Command::new("taskkill")
-                    .args(&["/PID", &pid.to_string(), "/F", "/T"])
-                    .output();
-
-                if let Err(e) = result {
-                    return Err(format!("Failed to execute taskkill: {}", e));
-                }
-            }
+fn refresh_binaries(app_handle: tauri::AppHandle, binaries: State<'_, BinaryStatusCache>) -> Result<BinaryStatus, String> {
+    binaries.refresh(&app_handle)
+}
 
-            // Clear PID atomically after kill attempt
-            *pid_lock = None;
-            return Ok(());
-        }
+#[tauri::command]
+async fn download_ytdlp(app_handle: tauri::AppHandle, binaries: State<'_, BinaryStatusCache>) -> Result<(), String> {
+    let result = BinaryManager::download_ytdlp(&app_handle).await;
+    binaries.invalidate();
+    result
+}
+
+#[tauri::command]
+async fn download_ffmpeg(app_handle: tauri::AppHandle, binaries: State<'_, BinaryStatusCache>) -> Result<(), String> {
+    let result = BinaryManager::download_ffmpeg(&app_handle).await;
+    binaries.invalidate();
+    result
+}
+
+#[tauri::command]
+async fn download_all_binaries(app_handle: tauri::AppHandle, binaries: State<'_, BinaryStatusCache>) -> Result<(), String> {
+    let result = async {
+        BinaryManager::download_ytdlp(&app_handle).await?;
+        BinaryManager::download_ffmpeg(&app_handle).await
     }
-    Err("No active download to cancel".to_string())
+    .await;
+    binaries.invalidate();
+    result
+}
+
+#[tauri::command]
+fn cancel_download() -> Result<(), String> {
+    downloader::terminate_current_download()
 }
@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+use tauri::Manager;
+
+/// `identifier` from tauri.conf.json, which names the app's directories
+const APP_IDENTIFIER: &str = "ezdl.tauri.dev";
+
+/// Where the app keeps its files. Resolvable without a running Tauri app, so the
+/// command line shares config, history and binaries with the GUI.
+#[derive(Clone, Debug)]
+pub struct AppPaths {
+    pub config_dir: PathBuf,
+    pub data_dir: PathBuf,
+}
+
+impl AppPaths {
+    pub fn from_app(app_handle: &tauri::AppHandle) -> Result<Self, String> {
+        let path = app_handle.path();
+        Ok(Self {
+            config_dir: path.app_config_dir()
+                .map_err(|e| format!("Failed to get app config directory: {}", e))?,
+            data_dir: path.app_data_dir()
+                .map_err(|e| format!("Failed to get app data directory: {}", e))?,
+        })
+    }
+
+    /// The directories Tauri resolves for this app, without an app handle
+    pub fn headless() -> Result<Self, String> {
+        Ok(Self {
+            config_dir: dirs_next::config_dir()
+                .ok_or("Failed to get config directory")?
+                .join(APP_IDENTIFIER),
+            data_dir: dirs_next::data_dir()
+                .ok_or("Failed to get data directory")?
+                .join(APP_IDENTIFIER),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifier_matches_tauri_config() {
+        let config: serde_json::Value = serde_json::from_str(include_str!("../tauri.conf.json")).unwrap();
+        assert_eq!(config["identifier"].as_str(), Some(APP_IDENTIFIER));
+    }
+}
@@ -0,0 +1,412 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::Manager;
+use crate::config::{ConfigState, UserConfig};
+use crate::subscriptions::SubscriptionStore;
+
+const AUDIO_FORMATS: [&str; 9] = ["best", "aac", "alac", "flac", "m4a", "mp3", "opus", "vorbis", "wav"];
+const MERGE_FORMATS: [&str; 6] = ["avi", "flv", "mkv", "mov", "mp4", "webm"];
+
+/// Named bundle of download settings, stored in `UserConfig::presets`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DownloadPreset {
+    /// yt-dlp format selector (`-f`), yt-dlp's default when unset
+    pub format: Option<String>,
+    pub audio_only: bool,
+    pub audio_format: String,
+    /// `--audio-quality` value, e.g. "0" (best VBR) or "192K"
+    pub audio_quality: Option<String>,
+    /// Container for merged video downloads
+    pub merge_format: String,
+    /// yt-dlp output template, relative to the download directory
+    pub output_template: String,
+    pub playlist: bool,
+    pub sponsorblock: bool,
+    /// Browser to read cookies from, e.g. "firefox"
+    pub cookies_from_browser: Option<String>,
+    /// Subtitle languages to download and embed, e.g. "en.*,de"
+    pub subtitles: Option<String>,
+    /// Download directory, the configured default when unset
+    pub download_dir: Option<String>,
+    /// Ignore the download archive and overwrite existing files
+    pub force_download: bool,
+}
+
+impl Default for DownloadPreset {
+    fn default() -> Self {
+        Self {
+            format: None,
+            audio_only: false,
+            audio_format: "mp3".to_string(),
+            audio_quality: None,
+            merge_format: "mkv".to_string(),
+            output_template: "%(title)s.%(ext)s".to_string(),
+            playlist: false,
+            sponsorblock: false,
+            cookies_from_browser: None,
+            subtitles: None,
+            download_dir: None,
+            force_download: false,
+        }
+    }
+}
+
+/// Per-download changes on top of a preset. Unset fields keep the preset's value,
+/// an empty string clears an optional preset value.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct PresetOverrides {
+    pub format: Option<String>,
+    pub audio_only: Option<bool>,
+    pub audio_format: Option<String>,
+    pub audio_quality: Option<String>,
+    pub merge_format: Option<String>,
+    pub output_template: Option<String>,
+    pub playlist: Option<bool>,
+    pub sponsorblock: Option<bool>,
+    pub cookies_from_browser: Option<String>,
+    pub subtitles: Option<String>,
+    pub download_dir: Option<String>,
+    pub force_download: Option<bool>,
+}
+
+// Override an optional value, treating an empty string as "unset"
+fn override_optional(target: &mut Option<String>, value: Option<String>) {
+    if let Some(value) = value {
+        let value = value.trim();
+        *target = if value.is_empty() { None } else { Some(value.to_string()) };
+    }
+}
+
+impl DownloadPreset {
+    pub fn with_overrides(mut self, overrides: PresetOverrides) -> Self {
+        override_optional(&mut self.format, overrides.format);
+        override_optional(&mut self.audio_quality, overrides.audio_quality);
+        override_optional(&mut self.cookies_from_browser, overrides.cookies_from_browser);
+        override_optional(&mut self.subtitles, overrides.subtitles);
+        override_optional(&mut self.download_dir, overrides.download_dir);
+
+        if let Some(audio_only) = overrides.audio_only {
+            self.audio_only = audio_only;
+        }
+        if let Some(audio_format) = overrides.audio_format {
+            self.audio_format = audio_format;
+        }
+        if let Some(merge_format) = overrides.merge_format {
+            self.merge_format = merge_format;
+        }
+        if let Some(output_template) = overrides.output_template {
+            self.output_template = output_template;
+        }
+        if let Some(playlist) = overrides.playlist {
+            self.playlist = playlist;
+        }
+        if let Some(sponsorblock) = overrides.sponsorblock {
+            self.sponsorblock = sponsorblock;
+        }
+        if let Some(force_download) = overrides.force_download {
+            self.force_download = force_download;
+        }
+
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !AUDIO_FORMATS.contains(&self.audio_format.as_str()) {
+            return Err(format!(
+                "Unsupported audio format \"{}\", expected one of: {}",
+                self.audio_format,
+                AUDIO_FORMATS.join(", ")
+            ));
+        }
+        if !MERGE_FORMATS.contains(&self.merge_format.as_str()) {
+            return Err(format!(
+                "Unsupported video container \"{}\", expected one of: {}",
+                self.merge_format,
+                MERGE_FORMATS.join(", ")
+            ));
+        }
+        if self.output_template.trim().is_empty() {
+            return Err("Output template must not be empty".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Binaries each post-processing step of this preset runs, with a label for errors
+    pub fn required_tools(&self) -> Vec<(&'static str, &'static [&'static str])> {
+        let mut steps: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+        if self.audio_only {
+            steps.push(("Audio conversion", &["ffmpeg", "ffprobe"]));
+        }
+        if self.sponsorblock {
+            steps.push(("SponsorBlock segment removal", &["ffmpeg", "ffprobe"]));
+        }
+        if self.subtitles.is_some() && !self.audio_only {
+            steps.push(("Subtitle embedding", &["ffmpeg"]));
+        }
+        steps
+    }
+
+    /// yt-dlp options for format selection and post-processing
+    pub fn ytdlp_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(ref format) = self.format {
+            args.extend(["-f".to_string(), format.clone()]);
+        }
+
+        if self.audio_only {
+            args.extend(["-x".to_string(), "--audio-format".to_string(), self.audio_format.clone()]);
+            if let Some(ref quality) = self.audio_quality {
+                args.extend(["--audio-quality".to_string(), quality.clone()]);
+            }
+        } else {
+            args.extend(["--merge-output-format".to_string(), self.merge_format.clone()]);
+            if let Some(ref langs) = self.subtitles {
+                args.extend([
+                    "--write-subs".to_string(),
+                    "--sub-langs".to_string(),
+                    langs.clone(),
+                    "--embed-subs".to_string(),
+                ]);
+            }
+        }
+
+        args.push(if self.playlist { "--yes-playlist" } else { "--no-playlist" }.to_string());
+
+        if self.sponsorblock {
+            args.extend(["--sponsorblock-remove".to_string(), "all".to_string()]);
+        }
+        if let Some(ref browser) = self.cookies_from_browser {
+            args.extend(["--cookies-from-browser".to_string(), browser.clone()]);
+        }
+        if self.force_download {
+            args.push("--force-overwrites".to_string());
+        }
+
+        args
+    }
+}
+
+pub struct PresetManager;
+
+impl PresetManager {
+    fn validate_name(name: &str) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Preset name must not be empty".to_string());
+        }
+        Ok(name.to_string())
+    }
+
+    /// Resolve a preset by name (built-in defaults when None) and apply overrides
+    pub fn resolve(
+        presets: &BTreeMap<String, DownloadPreset>,
+        name: Option<&str>,
+        overrides: PresetOverrides,
+    ) -> Result<DownloadPreset, String> {
+        let base = match name.map(str::trim).filter(|n| !n.is_empty()) {
+            Some(name) => presets
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("Unknown preset: {}", name))?,
+            None => DownloadPreset::default(),
+        };
+
+        let preset = base.with_overrides(overrides);
+        preset.validate()?;
+        Ok(preset)
+    }
+
+    // Create or replace a preset
+    pub fn save(
+        app_handle: &tauri::AppHandle,
+        config: &ConfigState,
+        name: &str,
+        preset: DownloadPreset,
+    ) -> Result<(), String> {
+        let name = Self::validate_name(name)?;
+        preset.validate()?;
+
+        config.update(app_handle, |config| {
+            config.presets.insert(name, preset);
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Refuses while site rules, subscriptions or the clipboard monitor use the preset,
+    /// naming them so the user knows what to change
+    pub fn delete(app_handle: &tauri::AppHandle, config: &ConfigState, name: &str) -> Result<(), String> {
+        let current = config.get();
+        let mut users: Vec<String> = current
+            .site_rules
+            .iter()
+            .filter(|rule| rule.preset.as_deref() == Some(name))
+            .map(|rule| format!("site rule \"{}\"", rule.name))
+            .collect();
+        users.extend(
+            app_handle
+                .state::<SubscriptionStore>()
+                .using_preset(name)
+                .into_iter()
+                .map(|subscription| format!("subscription \"{}\"", subscription)),
+        );
+        if current.clipboard.preset.as_deref() == Some(name) {
+            users.push("the clipboard monitor".to_string());
+        }
+        if !users.is_empty() {
+            return Err(format!(
+                "Preset \"{}\" is used by {}. Choose another preset there first.",
+                name,
+                users.join(", ")
+            ));
+        }
+
+        config.update(app_handle, |config| {
+            config
+                .presets
+                .remove(name)
+                .map(|_| ())
+                .ok_or_else(|| format!("Unknown preset: {}", name))
+        })?;
+        Ok(())
+    }
+
+    // Rename the preset and point site rules and the clipboard monitor at the new name
+    fn rename_in_config(config: &mut UserConfig, old_name: &str, new_name: &str) -> Result<(), String> {
+        if config.presets.contains_key(new_name) {
+            return Err(format!("A preset named \"{}\" already exists", new_name));
+        }
+        let preset = config
+            .presets
+            .remove(old_name)
+            .ok_or_else(|| format!("Unknown preset: {}", old_name))?;
+        config.presets.insert(new_name.to_string(), preset);
+
+        for rule in config.site_rules.iter_mut().filter(|rule| rule.preset.as_deref() == Some(old_name)) {
+            rule.preset = Some(new_name.to_string());
+        }
+        if config.clipboard.preset.as_deref() == Some(old_name) {
+            config.clipboard.preset = Some(new_name.to_string());
+        }
+        Ok(())
+    }
+
+    /// Rename a preset along with every site rule, subscription and setting using it
+    pub fn rename(
+        app_handle: &tauri::AppHandle,
+        config: &ConfigState,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), String> {
+        let new_name = Self::validate_name(new_name)?;
+        let current = config.get();
+        if current.presets.contains_key(&new_name) {
+            return Err(format!("A preset named \"{}\" already exists", new_name));
+        }
+        if !current.presets.contains_key(old_name) {
+            return Err(format!("Unknown preset: {}", old_name));
+        }
+
+        // Subscriptions live in their own file, moved back if the config can't be saved
+        let subscriptions = app_handle.state::<SubscriptionStore>();
+        subscriptions.rename_preset(old_name, &new_name)?;
+
+        let result = config.update(app_handle, |config| Self::rename_in_config(config, old_name, &new_name));
+
+        if let Err(e) = result {
+            if let Err(revert) = subscriptions.rename_preset(&new_name, old_name) {
+                eprintln!("Warning: {}", revert);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::DownloadArchive;
+    use crate::paths::AppPaths;
+    use crate::site_rules::SiteRule;
+    use crate::subscriptions::Subscription;
+
+    fn temp_paths(name: &str) -> AppPaths {
+        let dir = std::env::temp_dir().join(format!("ezdl-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        AppPaths { config_dir: dir.join("config"), data_dir: dir.join("data") }
+    }
+
+    #[test]
+    fn rename_updates_rules_and_subscriptions() {
+        let mut config = UserConfig::default();
+        config.presets.insert("music".to_string(), DownloadPreset::default());
+        config.site_rules = vec![
+            SiteRule {
+                pattern: "soundcloud.com".to_string(),
+                preset: Some("music".to_string()),
+                ..SiteRule::default()
+            },
+            SiteRule { pattern: "vimeo.com".to_string(), ..SiteRule::default() },
+        ];
+        config.clipboard.preset = Some("music".to_string());
+
+        let paths = temp_paths("rename-preset");
+        std::fs::create_dir_all(&paths.config_dir).unwrap();
+        let subscriptions = vec![Subscription {
+            id: 1,
+            name: "Mixes".to_string(),
+            preset: Some("music".to_string()),
+            ..Subscription::default()
+        }];
+        std::fs::write(
+            paths.config_dir.join("subscriptions.json"),
+            serde_json::to_string(&subscriptions).unwrap(),
+        ).unwrap();
+        let store = SubscriptionStore::load(&paths).unwrap();
+
+        store.rename_preset("music", "Music (MP3)").unwrap();
+        PresetManager::rename_in_config(&mut config, "music", "Music (MP3)").unwrap();
+
+        assert!(config.presets.contains_key("Music (MP3)") && !config.presets.contains_key("music"));
+        assert_eq!(config.site_rules[0].preset.as_deref(), Some("Music (MP3)"));
+        assert_eq!(config.site_rules[1].preset, None);
+        assert_eq!(config.clipboard.preset.as_deref(), Some("Music (MP3)"));
+        assert_eq!(store.using_preset("Music (MP3)"), ["Mixes"]);
+        // The new name is saved, not only kept in memory
+        assert_eq!(SubscriptionStore::load(&paths).unwrap().using_preset("Music (MP3)"), ["Mixes"]);
+
+        let _ = std::fs::remove_dir_all(paths.config_dir.parent().unwrap());
+    }
+
+    #[test]
+    fn rename_refuses_existing_names() {
+        let mut config = UserConfig::default();
+        config.presets.insert("music".to_string(), DownloadPreset::default());
+        config.presets.insert("video".to_string(), DownloadPreset::default());
+        assert!(PresetManager::rename_in_config(&mut config, "music", "video").is_err());
+        assert!(PresetManager::rename_in_config(&mut config, "podcasts", "talk").is_err());
+        assert!(config.presets.contains_key("music") && config.presets.contains_key("video"));
+
+        let paths = temp_paths("rename-archive");
+        for preset in ["music", "video"] {
+            let archive = DownloadArchive::path(&paths, Some(preset));
+            std::fs::create_dir_all(archive.parent().unwrap()).unwrap();
+            std::fs::write(&archive, format!("youtube {}\n", preset)).unwrap();
+        }
+        assert!(DownloadArchive::rename(&paths, "music", "video").is_err());
+        assert_eq!(
+            std::fs::read_to_string(DownloadArchive::path(&paths, Some("video"))).unwrap(),
+            "youtube video\n"
+        );
+        DownloadArchive::rename(&paths, "music", "Music (MP3)").unwrap();
+        assert!(!DownloadArchive::path(&paths, Some("music")).exists());
+        assert!(DownloadArchive::path(&paths, Some("Music (MP3)")).exists());
+
+        let _ = std::fs::remove_dir_all(paths.config_dir.parent().unwrap());
+    }
+}
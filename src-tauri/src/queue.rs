@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::Emitter;
+use crate::config::write_atomic;
+use crate::paths::AppPaths;
+use crate::presets::PresetOverrides;
+
+/// A download handed to the UI queue by a background source
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QueuedDownload {
+    pub url: String,
+    pub title: Option<String>,
+    pub preset: Option<String>,
+    /// Folder for this item, the UI's current folder when unset
+    pub download_dir: Option<String>,
+    /// What added the item, shown in the log, e.g. "subscription \"Podcast\""
+    pub source: String,
+}
+
+/// Payload of the `queue-add` event
+#[derive(Serialize, Clone, Debug)]
+pub struct QueueAdd {
+    pub items: Vec<QueuedDownload>,
+    /// Start processing the queue if it is idle
+    pub start: bool,
+}
+
+pub struct DownloadQueue;
+
+impl DownloadQueue {
+    /// Add items to the queue in the UI, which skips URLs it already holds
+    pub fn enqueue(app_handle: &tauri::AppHandle, items: Vec<QueuedDownload>, start: bool) {
+        if items.is_empty() {
+            return;
+        }
+        let _ = app_handle.emit("queue-add", QueueAdd { items, start });
+    }
+}
+
+/// A queue item as the UI keeps it, persisted so the command line can see it
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SavedQueueItem {
+    pub url: String,
+    pub title: Option<String>,
+    pub f_path: Option<String>,
+    pub preset: Option<String>,
+    pub mp3_only: bool,
+    pub enable_playlist: bool,
+    pub sponsorblock: bool,
+    pub cookies: bool,
+    pub force_download: bool,
+}
+
+impl SavedQueueItem {
+    /// The overrides the UI sends with this item. `preset_browser` is kept when cookies are on.
+    pub fn overrides(&self, preset_browser: Option<&str>) -> PresetOverrides {
+        PresetOverrides {
+            audio_only: Some(self.mp3_only),
+            playlist: Some(self.enable_playlist),
+            sponsorblock: Some(self.sponsorblock),
+            force_download: Some(self.force_download),
+            cookies_from_browser: Some(if self.cookies {
+                preset_browser.unwrap_or("firefox").to_string()
+            } else {
+                String::new()
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+/// The remembered queue in `app_data_dir/queue.json`
+pub struct SavedQueue;
+
+impl SavedQueue {
+    fn path(paths: &AppPaths) -> PathBuf {
+        paths.data_dir.join("queue.json")
+    }
+
+    pub fn load(paths: &AppPaths) -> Result<Vec<SavedQueueItem>, String> {
+        match fs::read_to_string(Self::path(paths)) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| format!("Failed to parse saved queue: {}", e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(format!("Failed to read saved queue: {}", e)),
+        }
+    }
+
+    pub fn save(paths: &AppPaths, items: &[SavedQueueItem]) -> Result<(), String> {
+        fs::create_dir_all(&paths.data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+        let json = serde_json::to_string_pretty(items).map_err(|e| format!("Failed to serialize queue: {}", e))?;
+        write_atomic(&Self::path(paths), json.as_bytes())
+    }
+}
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::presets::{DownloadPreset, PresetOverrides};
+
+/// How `SiteRule::pattern` is matched
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleMatch {
+    /// Glob on the host name (`*` and `?` wildcards). A pattern without
+    /// wildcards also matches subdomains, so "soundcloud.com" covers
+    /// "m.soundcloud.com".
+    #[default]
+    Domain,
+    /// Regular expression searched in the full URL
+    Regex,
+}
+
+/// Settings applied automatically to downloads whose URL matches `pattern`.
+/// Rules are tried in order and the first match wins.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SiteRule {
+    pub name: String,
+    pub enabled: bool,
+    pub pattern: String,
+    pub match_type: RuleMatch,
+    /// Preset used when the download doesn't name one
+    pub preset: Option<String>,
+    pub download_dir: Option<String>,
+    /// Output template, e.g. "%(playlist_title)s/%(title)s.%(ext)s" for per-playlist folders
+    pub output_template: Option<String>,
+    /// Extra yt-dlp arguments appended after the preset's
+    pub extra_args: Vec<String>,
+}
+
+impl Default for SiteRule {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            enabled: true,
+            pattern: String::new(),
+            match_type: RuleMatch::Domain,
+            preset: None,
+            download_dir: None,
+            output_template: None,
+            extra_args: Vec::new(),
+        }
+    }
+}
+
+impl SiteRule {
+    pub fn matches(&self, url: &str) -> Result<bool, String> {
+        match self.match_type {
+            RuleMatch::Domain => {
+                let host = match url::Url::parse(url.trim()) {
+                    Ok(parsed) => parsed.host_str().unwrap_or_default().to_lowercase(),
+                    Err(_) => return Ok(false),
+                };
+                let pattern = self.pattern.trim().to_lowercase();
+
+                if pattern.contains(['*', '?']) {
+                    Ok(glob_match(&pattern, &host))
+                } else {
+                    Ok(host == pattern || host.ends_with(&format!(".{}", pattern)))
+                }
+            }
+            RuleMatch::Regex => {
+                let regex = regex::Regex::new(&self.pattern)
+                    .map_err(|e| format!("Invalid pattern in site rule \"{}\": {}", self.name, e))?;
+                Ok(regex.is_match(url))
+            }
+        }
+    }
+
+    /// Preset changes this rule makes, applied beneath the caller's own overrides
+    pub fn overrides(&self) -> PresetOverrides {
+        PresetOverrides {
+            download_dir: self.download_dir.clone(),
+            output_template: self.output_template.clone(),
+            ..PresetOverrides::default()
+        }
+    }
+
+    pub fn validate(&self, presets: &BTreeMap<String, DownloadPreset>) -> Result<(), String> {
+        if self.pattern.trim().is_empty() {
+            return Err("Pattern must not be empty".to_string());
+        }
+        if self.match_type == RuleMatch::Regex {
+            regex::Regex::new(&self.pattern).map_err(|e| format!("Invalid regex: {}", e))?;
+        }
+        if let Some(ref preset) = self.preset {
+            if !presets.contains_key(preset) {
+                return Err(format!("Unknown preset: {}", preset));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Wildcard match where `*` matches any run of characters and `?` a single one
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the last `*` swallow one more character
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+pub struct SiteRules;
+
+impl SiteRules {
+    /// First enabled rule matching the URL
+    pub fn find<'a>(rules: &'a [SiteRule], url: &str) -> Result<Option<&'a SiteRule>, String> {
+        for rule in rules.iter().filter(|rule| rule.enabled) {
+            if rule.matches(url)? {
+                return Ok(Some(rule));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domain_rule(pattern: &str) -> SiteRule {
+        SiteRule { pattern: pattern.to_string(), ..SiteRule::default() }
+    }
+
+    #[test]
+    fn wildcard_subdomains() {
+        assert!(glob_match("*.example.com", "www.example.com"));
+        assert!(glob_match("*.example.com", "a.b.example.com"));
+        assert!(!glob_match("*.example.com", "example.com"));
+        assert!(!glob_match("*.example.com", "example.com.evil.net"));
+
+        let rule = domain_rule("*.Example.com");
+        assert_eq!(rule.matches("https://media.example.com/watch?v=1"), Ok(true));
+        assert_eq!(rule.matches("https://notexample.com/watch?v=1"), Ok(false));
+    }
+
+    #[test]
+    fn bare_host_matches_subdomains() {
+        let rule = domain_rule("soundcloud.com");
+        assert_eq!(rule.matches("https://soundcloud.com/artist/track"), Ok(true));
+        assert_eq!(rule.matches("https://m.soundcloud.com/artist/track"), Ok(true));
+        assert_eq!(rule.matches("https://notsoundcloud.com/artist/track"), Ok(false));
+        assert_eq!(rule.matches("not a url"), Ok(false));
+    }
+
+    #[test]
+    fn star_spans_path_segments() {
+        assert!(glob_match("youtube.com/*/videos", "youtube.com/@channel/videos"));
+        assert!(glob_match("youtube.com/*/videos", "youtube.com/c/channel/videos"));
+        assert!(glob_match("vimeo.com/*", "vimeo.com/"));
+        assert!(glob_match("v?meo.com*", "vimeo.com/76979871"));
+        assert!(!glob_match("youtube.com/*/videos", "youtube.com/@channel/shorts"));
+    }
+
+    #[test]
+    fn no_match() {
+        assert!(!glob_match("*.example.org", "www.example.com"));
+        assert!(!glob_match("ex?mple.com", "exmple.com"));
+        assert!(!glob_match("", "example.com"));
+        assert!(glob_match("*", ""));
+
+        // Domain rules only look at the host, a path in the pattern can't match
+        assert_eq!(domain_rule("youtube.com/*").matches("https://youtube.com/watch?v=1"), Ok(false));
+    }
+}
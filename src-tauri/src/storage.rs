@@ -0,0 +1,198 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Why a download directory can't be used as-is
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DirProblem {
+    Missing,
+    NotADirectory,
+    NotWritable,
+    /// Usable, but free space is below `UserConfig::low_disk_space_mb`
+    LowSpace,
+}
+
+/// Result of checking a download directory
+#[derive(Serialize, Clone, Debug)]
+pub struct DirValidation {
+    pub path: String,
+    pub problem: Option<DirProblem>,
+    pub free_bytes: Option<u64>,
+    pub message: String,
+}
+
+impl DirValidation {
+    /// Downloads can go here (possibly with little space left)
+    pub fn is_usable(&self) -> bool {
+        matches!(self.problem, None | Some(DirProblem::LowSpace))
+    }
+}
+
+pub struct Storage;
+
+impl Storage {
+    /// Free space available to this user on the filesystem holding `path`.
+    /// Paths that don't exist yet are measured at their nearest existing parent.
+    pub fn available_space(path: &Path) -> Option<u64> {
+        let existing = path.ancestors().find(|p| p.exists())?;
+        fs2::available_space(existing).ok()
+    }
+
+    /// Block until the filesystem holding `path` has `min_free_bytes` free again
+    pub fn wait_for_space(path: &Path, min_free_bytes: u64) {
+        while Self::available_space(path).is_some_and(|free| free < min_free_bytes) {
+            std::thread::sleep(Duration::from_secs(30));
+        }
+    }
+
+    // Check write access without leaving files behind
+    #[cfg(unix)]
+    fn is_writable(path: &Path) -> bool {
+        use std::os::unix::ffi::OsStrExt;
+
+        let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        // access() also reports read-only mounts (EROFS)
+        unsafe { libc::access(c_path.as_ptr(), libc::W_OK | libc::X_OK) == 0 }
+    }
+
+    #[cfg(windows)]
+    fn is_writable(path: &Path) -> bool {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        // Windows ignores the read-only attribute on directories, so probe with a
+        // file the OS deletes as soon as the handle closes, even if we crash
+        const FILE_FLAG_DELETE_ON_CLOSE: u32 = 0x0400_0000;
+        let probe = path.join(format!(".ezdl-probe-{}", std::process::id()));
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .custom_flags(FILE_FLAG_DELETE_ON_CLOSE)
+            .open(probe)
+            .is_ok()
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn is_writable(_path: &Path) -> bool {
+        true
+    }
+
+    pub fn validate_dir(dir: &str, min_free_bytes: u64) -> DirValidation {
+        let path = Path::new(dir);
+        let result = |problem: Option<DirProblem>, free_bytes: Option<u64>, message: String| DirValidation {
+            path: dir.to_string(),
+            problem,
+            free_bytes,
+            message,
+        };
+
+        match fs::metadata(path) {
+            Err(_) => {
+                return result(Some(DirProblem::Missing), None, format!("Download folder does not exist: {}", dir));
+            }
+            Ok(metadata) if !metadata.is_dir() => {
+                return result(Some(DirProblem::NotADirectory), None, format!("Download folder is not a directory: {}", dir));
+            }
+            Ok(_) => {}
+        }
+
+        let free_bytes = Self::available_space(path);
+
+        if !Self::is_writable(path) {
+            return result(Some(DirProblem::NotWritable), free_bytes, format!("Download folder is not writable: {}", dir));
+        }
+
+        match free_bytes {
+            Some(free) if free < min_free_bytes => result(
+                Some(DirProblem::LowSpace),
+                free_bytes,
+                format!("Only {} free in {}", Self::format_bytes(free), dir),
+            ),
+            _ => result(None, free_bytes, "Download folder is ready".to_string()),
+        }
+    }
+
+    pub fn create_dir(dir: &str) -> Result<(), String> {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir, e))
+    }
+
+    pub fn format_bytes(bytes: u64) -> String {
+        const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+        let mut value = bytes as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            format!("{} B", bytes)
+        } else {
+            format!("{:.1} {}", value, UNITS[unit])
+        }
+    }
+}
+
+/// Payload of the `disk-low` event
+#[derive(Serialize, Clone, Debug)]
+pub struct DiskLow {
+    pub path: String,
+    pub free_bytes: u64,
+    pub threshold_bytes: u64,
+    /// Estimated size of the download that didn't fit, when checked up front
+    pub required_bytes: Option<u64>,
+    pub message: String,
+}
+
+/// Polls free space on a download's filesystem and calls `on_low` once when it
+/// drops below the threshold. Stops when dropped.
+pub struct DiskMonitor {
+    stop: Arc<AtomicBool>,
+    tripped: Arc<AtomicBool>,
+}
+
+impl DiskMonitor {
+    const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+    pub fn start<F>(dir: PathBuf, min_free_bytes: u64, on_low: F) -> Self
+    where
+        F: FnOnce(u64) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let tripped = Arc::new(AtomicBool::new(false));
+
+        std::thread::spawn({
+            let stop = Arc::clone(&stop);
+            let tripped = Arc::clone(&tripped);
+            move || {
+                while !stop.load(Ordering::Relaxed) {
+                    if let Some(free) = Storage::available_space(&dir) {
+                        if free < min_free_bytes {
+                            tripped.store(true, Ordering::Relaxed);
+                            on_low(free);
+                            return;
+                        }
+                    }
+                    std::thread::sleep(Self::POLL_INTERVAL);
+                }
+            }
+        });
+
+        Self { stop, tripped }
+    }
+
+    /// Whether free space fell below the threshold while monitoring
+    pub fn tripped(&self) -> bool {
+        self.tripped.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for DiskMonitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,480 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use crate::archive::DownloadArchive;
+use crate::binary_manager::BinaryStatusCache;
+use crate::config::{write_atomic, ConfigState};
+use crate::downloader;
+use crate::feeds::{FeedFetch, FeedPoller};
+use crate::history::now_secs;
+use crate::paths::AppPaths;
+use crate::queue::{DownloadQueue, QueuedDownload};
+
+const MIN_INTERVAL_MINUTES: u64 = 5;
+
+/// How a subscription's URL is checked
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriptionSource {
+    /// Channel or playlist listed by yt-dlp
+    #[default]
+    Playlist,
+    /// RSS or Atom feed, e.g. a podcast or a YouTube channel feed
+    Feed,
+}
+
+/// A channel, playlist or feed checked periodically for new videos
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Subscription {
+    /// Assigned when the subscription is first saved
+    pub id: u64,
+    pub name: String,
+    pub source: SubscriptionSource,
+    /// Playlist, channel or feed URL. For YouTube channels use the /videos tab.
+    pub url: String,
+    pub preset: Option<String>,
+    pub download_dir: Option<String>,
+    pub enabled: bool,
+    pub interval_minutes: u64,
+    /// Only look at the first this many entries on each check, 0 for all
+    pub scan_limit: u32,
+    /// Queue what is already in the list on the first check, instead of only later additions
+    pub download_existing: bool,
+    /// Unix timestamp of the last check
+    pub last_checked: Option<u64>,
+    pub last_error: Option<String>,
+    /// Ids of entries that are never queued again although the download archive
+    /// doesn't know them: what was skipped on the first check and queued entries
+    /// without an archive id. Newest first, limited to the latest listing plus
+    /// `SEEN_TAIL` older ids.
+    pub seen_ids: Vec<String>,
+    /// HTTP validators from the last feed fetch
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Default for Subscription {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            name: String::new(),
+            source: SubscriptionSource::Playlist,
+            url: String::new(),
+            preset: None,
+            download_dir: None,
+            enabled: true,
+            interval_minutes: 360,
+            scan_limit: 50,
+            download_existing: false,
+            last_checked: None,
+            last_error: None,
+            seen_ids: Vec::new(),
+            etag: None,
+            last_modified: None,
+        }
+    }
+}
+
+impl Subscription {
+    fn is_due(&self, now: u64) -> bool {
+        self.enabled && self.last_checked.is_none_or(|checked| now >= checked + self.interval_minutes * 60)
+    }
+}
+
+/// An entry of `--flat-playlist` output
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct FlatEntry {
+    id: Option<String>,
+    url: Option<String>,
+    webpage_url: Option<String>,
+    title: Option<String>,
+    ie_key: Option<String>,
+}
+
+/// A video found by checking a subscription
+struct Entry {
+    /// Id recorded in `Subscription::seen_ids`
+    key: String,
+    url: String,
+    title: Option<String>,
+    /// Extractor and id, when known, to look the video up in the download archive
+    archive_id: Option<(String, String)>,
+}
+
+/// Entries of a subscription and the feed validators that came with them
+struct Listing {
+    entries: Vec<Entry>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Subscriptions and their sync state, stored in `app_config_dir/subscriptions.json`
+pub struct SubscriptionStore {
+    path: PathBuf,
+    subscriptions: Mutex<Vec<Subscription>>,
+    // One check at a time, so manual and scheduled checks can't queue the same videos
+    sync_lock: Mutex<()>,
+}
+
+impl SubscriptionStore {
+    const TICK: Duration = Duration::from_secs(60);
+    /// Seen ids kept beyond the latest listing, for entries that briefly drop out of it
+    const SEEN_TAIL: usize = 200;
+
+    pub fn load(paths: &AppPaths) -> Result<Self, String> {
+        let path = paths.config_dir.join("subscriptions.json");
+
+        let subscriptions = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                // Keep the unreadable file for the user instead of overwriting it on the next save
+                let backup = path.with_file_name(format!("subscriptions.corrupt-{}.json", now_secs()));
+                eprintln!("Warning: Failed to parse subscriptions ({}), moved to {}", e, backup.display());
+                let _ = fs::rename(&path, &backup);
+                Vec::new()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("Failed to read subscriptions: {}", e)),
+        };
+
+        Ok(Self {
+            path,
+            subscriptions: Mutex::new(subscriptions),
+            sync_lock: Mutex::new(()),
+        })
+    }
+
+    fn save(&self, subscriptions: &[Subscription]) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(subscriptions)
+            .map_err(|e| format!("Failed to serialize subscriptions: {}", e))?;
+        write_atomic(&self.path, content.as_bytes())
+            .map_err(|e| format!("Failed to save subscriptions: {}", e))
+    }
+
+    pub fn list(&self) -> Vec<Subscription> {
+        self.subscriptions.lock().unwrap().clone()
+    }
+
+    /// Create (id 0) or update a subscription, keeping its sync state
+    pub fn upsert(&self, app_handle: &tauri::AppHandle, mut subscription: Subscription) -> Result<Subscription, String> {
+        subscription.url = subscription.url.trim().to_string();
+        subscription.name = subscription.name.trim().to_string();
+
+        // Feeds may also be local files
+        let parsed = url::Url::parse(&subscription.url).map_err(|_| format!("Invalid URL: {}", subscription.url))?;
+        let local_feed = subscription.source == SubscriptionSource::Feed && parsed.scheme() == "file";
+        if !matches!(parsed.scheme(), "http" | "https") && !local_feed {
+            return Err(format!("Invalid URL: {}", subscription.url));
+        }
+        if subscription.name.is_empty() {
+            subscription.name = subscription.url.clone();
+        }
+        if subscription.interval_minutes < MIN_INTERVAL_MINUTES {
+            return Err(format!("Check interval must be at least {} minutes", MIN_INTERVAL_MINUTES));
+        }
+        if let Some(ref preset) = subscription.preset {
+            if !app_handle.state::<ConfigState>().get().presets.contains_key(preset) {
+                return Err(format!("Unknown preset: {}", preset));
+            }
+        }
+
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let mut updated = subscriptions.clone();
+
+        if subscription.id == 0 {
+            subscription.id = updated.iter().map(|s| s.id).max().unwrap_or(0) + 1;
+            subscription.last_checked = None;
+            subscription.last_error = None;
+            subscription.seen_ids.clear();
+            subscription.etag = None;
+            subscription.last_modified = None;
+            updated.push(subscription.clone());
+        } else {
+            let existing = updated
+                .iter_mut()
+                .find(|s| s.id == subscription.id)
+                .ok_or_else(|| format!("Unknown subscription: {}", subscription.id))?;
+            subscription.last_checked = existing.last_checked;
+            subscription.last_error = existing.last_error.clone();
+            subscription.seen_ids = std::mem::take(&mut existing.seen_ids);
+            subscription.etag = existing.etag.take();
+            subscription.last_modified = existing.last_modified.take();
+            *existing = subscription.clone();
+        }
+
+        self.save(&updated)?;
+        *subscriptions = updated;
+        Ok(subscription)
+    }
+
+    pub fn delete(&self, id: u64) -> Result<(), String> {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let mut updated = subscriptions.clone();
+        let before = updated.len();
+        updated.retain(|s| s.id != id);
+        if updated.len() == before {
+            return Err(format!("Unknown subscription: {}", id));
+        }
+
+        self.save(&updated)?;
+        *subscriptions = updated;
+        Ok(())
+    }
+
+    /// Names of subscriptions downloading with a preset
+    pub fn using_preset(&self, preset: &str) -> Vec<String> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.preset.as_deref() == Some(preset))
+            .map(|s| s.name.clone())
+            .collect()
+    }
+
+    /// Point subscriptions at a renamed preset
+    pub fn rename_preset(&self, old_name: &str, new_name: &str) -> Result<(), String> {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if !subscriptions.iter().any(|s| s.preset.as_deref() == Some(old_name)) {
+            return Ok(());
+        }
+        let mut updated = subscriptions.clone();
+        for subscription in updated.iter_mut().filter(|s| s.preset.as_deref() == Some(old_name)) {
+            subscription.preset = Some(new_name.to_string());
+        }
+
+        self.save(&updated)?;
+        *subscriptions = updated;
+        Ok(())
+    }
+
+    // Apply a change to one subscription and persist it
+    fn modify(&self, id: u64, change: impl FnOnce(&mut Subscription)) -> Result<(), String> {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let mut updated = subscriptions.clone();
+        if let Some(subscription) = updated.iter_mut().find(|s| s.id == id) {
+            change(subscription);
+        }
+
+        self.save(&updated)?;
+        *subscriptions = updated;
+        Ok(())
+    }
+
+    /// Check a subscription for videos that are neither seen before nor in the
+    /// preset's download archive, returning them oldest first
+    pub fn sync(&self, app_handle: &tauri::AppHandle, id: u64) -> Result<Vec<QueuedDownload>, String> {
+        let _guard = self.sync_lock.lock().unwrap();
+
+        let subscription = self.list()
+            .into_iter()
+            .find(|s| s.id == id)
+            .ok_or_else(|| format!("Unknown subscription: {}", id))?;
+
+        let listing = match Self::list_entries(app_handle, &subscription) {
+            Ok(Some(listing)) => listing,
+            Ok(None) => {
+                // Feed unchanged since the last fetch
+                self.modify(id, |s| {
+                    s.last_checked = Some(now_secs());
+                    s.last_error = None;
+                })?;
+                return Ok(Vec::new());
+            }
+            Err(e) => {
+                self.modify(id, |s| {
+                    s.last_checked = Some(now_secs());
+                    s.last_error = Some(e.clone());
+                })?;
+                return Err(e);
+            }
+        };
+
+        let archived = DownloadArchive::entries(&app_handle.state::<AppPaths>(), subscription.preset.as_deref())?;
+        let first_check = subscription.last_checked.is_none();
+
+        let mut items: Vec<QueuedDownload> = listing.entries
+            .iter()
+            .filter(|entry| !subscription.seen_ids.contains(&entry.key))
+            .filter(|entry| {
+                !archived.iter().any(|archived| {
+                    entry.archive_id.as_ref().is_some_and(|(extractor, video_id)| {
+                        extractor.eq_ignore_ascii_case(&archived.extractor) && *video_id == archived.video_id
+                    })
+                })
+            })
+            .map(|entry| QueuedDownload {
+                url: entry.url.clone(),
+                title: entry.title.clone(),
+                preset: subscription.preset.clone(),
+                download_dir: subscription.download_dir.clone(),
+                source: format!("subscription \"{}\"", subscription.name),
+            })
+            .collect();
+
+        // Queued entries are recognised by the archive once downloaded, the rest
+        // would be offered again on every check
+        let skip_existing = first_check && !subscription.download_existing;
+        let newly_seen = listing.entries
+            .iter()
+            .filter(|entry| skip_existing || entry.archive_id.is_none())
+            .map(|entry| entry.key.clone());
+        let seen_ids = Self::updated_seen_ids(&subscription.seen_ids, &listing.entries, newly_seen);
+
+        // Lists and feeds are newest first, download in publishing order
+        items.reverse();
+        if skip_existing {
+            items.clear();
+        }
+
+        self.modify(id, |s| {
+            s.seen_ids = seen_ids;
+            s.etag = listing.etag;
+            s.last_modified = listing.last_modified;
+            s.last_checked = Some(now_secs());
+            s.last_error = None;
+        })?;
+
+        Ok(items)
+    }
+
+    // Seen ids in listing order, then older ones that are no longer listed, up to `SEEN_TAIL` of them
+    fn updated_seen_ids(seen: &[String], listed: &[Entry], newly_seen: impl Iterator<Item = String>) -> Vec<String> {
+        let mut seen_ids: Vec<String> = seen.iter().cloned().chain(newly_seen).collect();
+        let in_listing = |id: &String| listed.iter().any(|entry| entry.key == *id);
+
+        let mut updated: Vec<String> = listed
+            .iter()
+            .map(|entry| &entry.key)
+            .filter(|key| seen_ids.contains(key))
+            .cloned()
+            .collect();
+        updated.dedup();
+        seen_ids.retain(|id| !in_listing(id));
+        updated.extend(seen_ids.into_iter().take(Self::SEEN_TAIL));
+        updated
+    }
+
+    // None when a feed hasn't changed since the last check
+    fn list_entries(app_handle: &tauri::AppHandle, subscription: &Subscription) -> Result<Option<Listing>, String> {
+        match subscription.source {
+            SubscriptionSource::Playlist => Self::list_playlist(app_handle, subscription).map(Some),
+            SubscriptionSource::Feed => Self::list_feed(subscription),
+        }
+    }
+
+    fn list_feed(subscription: &Subscription) -> Result<Option<Listing>, String> {
+        let fetch = FeedPoller::fetch(
+            &subscription.url,
+            subscription.etag.as_deref(),
+            subscription.last_modified.as_deref(),
+        )?;
+
+        let FeedFetch::Updated { items, etag, last_modified } = fetch else {
+            return Ok(None);
+        };
+
+        let entries = items
+            .into_iter()
+            .map(|item| Entry {
+                archive_id: item.video_id.map(|video_id| ("youtube".to_string(), video_id)),
+                key: item.id,
+                url: item.url,
+                title: item.title,
+            })
+            .collect();
+
+        Ok(Some(Listing { entries, etag, last_modified }))
+    }
+
+    fn list_playlist(app_handle: &tauri::AppHandle, subscription: &Subscription) -> Result<Listing, String> {
+        use std::process::Command;
+
+        let status = app_handle.state::<BinaryStatusCache>().get(app_handle)?;
+        if !status.yt_dlp_installed {
+            return Err("yt-dlp not found".to_string());
+        }
+        let ytdlp_path = status.yt_dlp_path.clone().unwrap_or_else(|| "yt-dlp".to_string());
+
+        let mut args = status.yt_dlp_args.clone();
+        args.extend([
+            "--flat-playlist".to_string(),
+            "--print".to_string(),
+            "%(.{id,url,webpage_url,title,ie_key})j".to_string(),
+        ]);
+        if subscription.scan_limit > 0 {
+            args.extend(["--playlist-end".to_string(), subscription.scan_limit.to_string()]);
+        }
+        args.extend(["--".to_string(), subscription.url.clone()]);
+
+        let output = Command::new(&ytdlp_path)
+            .args(&args)
+            .output()
+            .map_err(|e| format!("Failed to run yt-dlp: {}", e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("unknown error");
+            return Err(format!("Failed to list {}: {}", subscription.url, reason));
+        }
+
+        let entries = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| serde_json::from_str::<FlatEntry>(line).ok())
+            .filter_map(|entry| {
+                let id = entry.id?;
+                // Some extractors give bare IDs as the URL
+                let url = [entry.url, entry.webpage_url]
+                    .into_iter()
+                    .flatten()
+                    .find(|url| downloader::is_web_url(url))?;
+                Some(Entry {
+                    url,
+                    title: entry.title,
+                    archive_id: entry.ie_key.map(|key| (key, id.clone())),
+                    key: id,
+                })
+            })
+            .collect();
+
+        Ok(Listing { entries, etag: None, last_modified: None })
+    }
+
+    /// Check due subscriptions in the background and queue their new videos
+    pub fn start(app_handle: tauri::AppHandle) {
+        std::thread::spawn(move || loop {
+            std::thread::sleep(Self::TICK);
+
+            let store = app_handle.state::<SubscriptionStore>();
+            let now = now_secs();
+            let due: Vec<Subscription> = store.list().into_iter().filter(|s| s.is_due(now)).collect();
+
+            for subscription in due {
+                match store.sync(&app_handle, subscription.id) {
+                    Ok(items) => {
+                        if !items.is_empty() {
+                            let _ = app_handle.emit(
+                                "download-log",
+                                format!("{} new video(s) from subscription \"{}\"", items.len(), subscription.name),
+                            );
+                        }
+                        DownloadQueue::enqueue(&app_handle, items, true);
+                    }
+                    Err(e) => {
+                        let _ = app_handle.emit(
+                            "download-log",
+                            format!("Subscription \"{}\" check failed: {}", subscription.name, e),
+                        );
+                    }
+                }
+            }
+        });
+    }
+}
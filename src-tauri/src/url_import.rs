@@ -0,0 +1,302 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use crate::downloader;
+use crate::history::HistoryStore;
+use crate::presets::DownloadPreset;
+use crate::queue::QueuedDownload;
+
+/// A line of an imported file that couldn't be used
+#[derive(Serialize, Clone, Debug)]
+pub struct InvalidLine {
+    /// 1-based line number in the file
+    pub line: u64,
+    pub text: String,
+    pub reason: String,
+}
+
+/// Result of `import_urls`
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ImportReport {
+    /// Added to the queue
+    pub items: Vec<QueuedDownload>,
+    /// Already queued, or listed twice in the file
+    pub duplicates: usize,
+    /// Downloaded before according to the history
+    pub already_downloaded: usize,
+    pub invalid: Vec<InvalidLine>,
+}
+
+/// A URL read from the file, before checks against the queue and history
+#[derive(Debug, PartialEq)]
+struct ImportedUrl {
+    line: u64,
+    url: String,
+    title: Option<String>,
+    preset: Option<String>,
+    download_dir: Option<String>,
+}
+
+/// Reads lists of links: plain text, CSV and browser bookmark exports
+pub struct UrlImport;
+
+impl UrlImport {
+    pub fn import(
+        path: &Path,
+        presets: &BTreeMap<String, DownloadPreset>,
+        history: &HistoryStore,
+        queued: &[String],
+    ) -> Result<ImportReport, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let source = format!(
+            "import of {}",
+            path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default()
+        );
+
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let mut report = ImportReport::default();
+        let urls = Self::parse(&content, &extension, &mut report.invalid);
+
+        let downloaded = history.downloaded_urls()?;
+        let mut seen: HashSet<String> = queued.iter().map(|url| url.trim_end_matches('/').to_string()).collect();
+        for imported in urls {
+            if let Err(reason) = Self::validate(&imported, presets) {
+                report.invalid.push(InvalidLine { line: imported.line, text: imported.url, reason });
+                continue;
+            }
+            if !seen.insert(imported.url.trim_end_matches('/').to_string()) {
+                report.duplicates += 1;
+                continue;
+            }
+            if downloaded.contains(imported.url.trim_end_matches('/')) {
+                report.already_downloaded += 1;
+                continue;
+            }
+
+            report.items.push(QueuedDownload {
+                url: imported.url,
+                title: imported.title,
+                preset: imported.preset,
+                download_dir: imported.download_dir,
+                source: source.clone(),
+            });
+        }
+
+        report.invalid.sort_by_key(|invalid| invalid.line);
+        Ok(report)
+    }
+
+    // Pick the format by content for bookmark files, by extension otherwise
+    fn parse(content: &str, extension: &str, invalid: &mut Vec<InvalidLine>) -> Vec<ImportedUrl> {
+        if content.trim_start().starts_with("<!DOCTYPE NETSCAPE-Bookmark-file") || matches!(extension, "html" | "htm") {
+            Self::parse_bookmarks(content)
+        } else if matches!(extension, "csv" | "tsv") {
+            Self::parse_csv(content, invalid)
+        } else {
+            Self::parse_text(content)
+        }
+    }
+
+    fn validate(imported: &ImportedUrl, presets: &BTreeMap<String, DownloadPreset>) -> Result<(), String> {
+        let url = url::Url::parse(&imported.url).map_err(|_| "Not a URL".to_string())?;
+        if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+            return Err("Only http and https URLs can be downloaded".to_string());
+        }
+        if let Some(ref preset) = imported.preset {
+            if !presets.contains_key(preset) {
+                return Err(format!("Unknown preset \"{}\"", preset));
+            }
+        }
+        Ok(())
+    }
+
+    /// One URL per line; blank lines and lines starting with `#` or `//` are skipped
+    fn parse_text(content: &str) -> Vec<ImportedUrl> {
+        content
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index as u64 + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#') && !line.starts_with("//"))
+            .map(|(line, text)| ImportedUrl {
+                line,
+                url: text.to_string(),
+                title: None,
+                preset: None,
+                download_dir: None,
+            })
+            .collect()
+    }
+
+    /// Columns named by a header row: url, and optionally preset, output folder
+    /// and title. Without a header only the URLs are used, from whichever column
+    /// holds one.
+    fn parse_csv(content: &str, invalid: &mut Vec<InvalidLine>) -> Vec<ImportedUrl> {
+        // Spreadsheets in some locales export with semicolons
+        let first_line = content.lines().next().unwrap_or_default();
+        let delimiter = [b',', b';', b'\t']
+            .into_iter()
+            .max_by_key(|delimiter| first_line.bytes().filter(|byte| byte == delimiter).count())
+            .unwrap_or(b',');
+
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .comment(Some(b'#'))
+            .from_reader(content.as_bytes());
+
+        let mut columns = None;
+        let mut urls = Vec::new();
+        for (index, record) in reader.records().enumerate() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    invalid.push(InvalidLine {
+                        line: e.position().map_or(0, |position| position.line()),
+                        text: String::new(),
+                        reason: format!("Unreadable CSV row: {}", e),
+                    });
+                    continue;
+                }
+            };
+            let line = record.position().map_or(index as u64 + 1, |position| position.line());
+            let field = |column: Option<usize>| {
+                column
+                    .and_then(|column| record.get(column))
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::to_string)
+            };
+
+            // A header row names the columns instead of holding a URL
+            if index == 0 && record.iter().any(|name| name.trim().eq_ignore_ascii_case("url")) {
+                let position = |names: &[&str]| {
+                    record.iter().position(|name| names.iter().any(|wanted| name.trim().eq_ignore_ascii_case(wanted)))
+                };
+                columns = Some((
+                    position(&["url"]),
+                    position(&["preset"]),
+                    position(&["output", "folder", "dir", "download_dir"]),
+                    position(&["title", "name"]),
+                ));
+                continue;
+            }
+
+            let imported = match columns {
+                Some((url, preset, download_dir, title)) => field(url).map(|url| ImportedUrl {
+                    line,
+                    url,
+                    title: field(title),
+                    preset: field(preset),
+                    download_dir: field(download_dir),
+                }),
+                // Rows without a URL keep their first value, so they are reported as invalid
+                None => {
+                    let values = || record.iter().map(str::trim).filter(|value| !value.is_empty());
+                    values()
+                        .find(|value| downloader::is_web_url(value))
+                        .or_else(|| values().next())
+                        .map(|url| ImportedUrl { line, url: url.to_string(), title: None, preset: None, download_dir: None })
+                }
+            };
+            urls.extend(imported);
+        }
+        urls
+    }
+
+    /// Netscape bookmark files, as exported by every major browser, hold one
+    /// `<DT><A HREF="…">title</A>` per line
+    fn parse_bookmarks(content: &str) -> Vec<ImportedUrl> {
+        let selector = scraper::Selector::parse("a[href]").unwrap();
+        let mut urls = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            if !line.to_ascii_lowercase().contains("href") {
+                continue;
+            }
+            let fragment = scraper::Html::parse_fragment(line);
+            for link in fragment.select(&selector) {
+                let title: String = link.text().collect();
+                urls.push(ImportedUrl {
+                    line: index as u64 + 1,
+                    url: link.value().attr("href").unwrap_or_default().trim().to_string(),
+                    title: Some(title.trim().to_string()).filter(|title| !title.is_empty()),
+                    preset: None,
+                    download_dir: None,
+                });
+            }
+        }
+        urls
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> (Vec<ImportedUrl>, Vec<InvalidLine>) {
+        let path = format!("{}/tests/fixtures/imports/{}", env!("CARGO_MANIFEST_DIR"), name);
+        let content = std::fs::read_to_string(&path).unwrap();
+        let extension = name.rsplit_once('.').map_or("", |(_, extension)| extension);
+        let mut invalid = Vec::new();
+        let urls = UrlImport::parse(&content, extension, &mut invalid);
+        (urls, invalid)
+    }
+
+    fn imported(line: u64, url: &str) -> ImportedUrl {
+        ImportedUrl { line, url: url.to_string(), title: None, preset: None, download_dir: None }
+    }
+
+    #[test]
+    fn csv_with_header_reads_named_columns() {
+        let (urls, invalid) = fixture("with-header.csv");
+        assert!(invalid.is_empty());
+        assert_eq!(urls, vec![
+            ImportedUrl {
+                title: Some("Never Gonna Give You Up".to_string()),
+                preset: Some("music".to_string()),
+                ..imported(2, "https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+            },
+            ImportedUrl {
+                download_dir: Some("/videos/vimeo".to_string()),
+                ..imported(3, "https://vimeo.com/76979871")
+            },
+        ]);
+    }
+
+    #[test]
+    fn csv_without_header_finds_the_url_column() {
+        let (urls, invalid) = fixture("no-header.csv");
+        assert!(invalid.is_empty());
+        assert_eq!(urls, vec![
+            imported(1, "https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            imported(2, "https://vimeo.com/76979871"),
+            imported(3, "not a link"),
+        ]);
+    }
+
+    #[test]
+    fn text_skips_comments_and_blank_lines() {
+        let (urls, _) = fixture("links.txt");
+        assert_eq!(urls, vec![
+            imported(2, "https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            imported(5, "https://vimeo.com/76979871"),
+        ]);
+    }
+
+    #[test]
+    fn bookmarks_keep_titles() {
+        let (urls, _) = fixture("bookmarks.html");
+        assert_eq!(urls, vec![
+            ImportedUrl {
+                title: Some("Never Gonna Give You Up".to_string()),
+                ..imported(7, "https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+            },
+            imported(9, "https://vimeo.com/76979871"),
+        ]);
+    }
+}
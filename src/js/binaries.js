@@ -7,7 +7,7 @@ import { invoke } from '@tauri-apps/api/core';
 export class BinaryManager {
   /**
    * Check status of required binaries
-   * @returns {Promise<{yt_dlp_installed: boolean, ffmpeg_installed: boolean, yt_dlp_path: string|null, ffmpeg_path: string|null}>}
+   * @returns {Promise<{yt_dlp_installed: boolean, ffmpeg_installed: boolean, ffprobe_installed: boolean, yt_dlp_path: string|null, ffmpeg_path: string|null, ffprobe_path: string|null}>}
    */
   static async checkBinaries() {
     try {
@@ -18,6 +18,19 @@ export class BinaryManager {
     }
   }
 
+  /**
+   * Re-detect binaries, bypassing the backend's cached status
+   * @returns {Promise<Object>} Same shape as checkBinaries()
+   */
+  static async refreshBinaries() {
+    try {
+      return await invoke('refresh_binaries');
+    } catch (error) {
+      console.error('Failed to refresh binaries:', error);
+      throw error;
+    }
+  }
+
   /**
    * Download yt-dlp binary
    * @returns {Promise<void>}
@@ -57,7 +70,7 @@ export class BinaryManager {
       await this.downloadYtDlp();
     }
 
-    if (!status.ffmpeg_installed) {
+    if (!status.ffmpeg_installed || !status.ffprobe_installed) {
       if (onProgress) onProgress('Downloading ffmpeg...');
       await this.downloadFfmpeg();
     }
@@ -69,6 +82,6 @@ export class BinaryManager {
    */
   static async areAllBinariesInstalled() {
     const status = await this.checkBinaries();
-    return status.yt_dlp_installed && status.ffmpeg_installed;
+    return status.yt_dlp_installed && status.ffmpeg_installed && status.ffprobe_installed;
   }
 }
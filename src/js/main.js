@@ -24,6 +24,8 @@ const mp3OnlyCheckbox = document.getElementById('mp3Only');
 const sponsorblockCheckbox = document.getElementById('sponsorblock');
 const enablePlayistCheckbox = document.getElementById('enablePlaylist');
 const cookiesCheckbox = document.getElementById('cookies');
+const forceDownloadCheckbox = document.getElementById('forceDownload');
+const presetSelect = document.getElementById('presetSelect');
 const downloadBtn = document.getElementById('downloadBtn');
 const addToQueueBtn = document.getElementById('addToQueueBtn');
 const browseBtn = document.getElementById('browseBtn');
@@ -32,6 +34,9 @@ const clearQueueBtn = document.getElementById('clearQueueBtn');
 const log = document.getElementById('log');
 const statusText = document.getElementById("statusText");
 const statusPercent = document.getElementById("statusPercent");
+const fileActions = document.getElementById("fileActions");
+const openFileBtn = document.getElementById("openFileBtn");
+const showInFolderBtn = document.getElementById("showInFolderBtn");
 
 // State variables
 const queue = [];
@@ -42,16 +47,22 @@ let originalUrl = '';
 let isProgrammaticChange = false;
 let currentItem = null;
 let shouldStopQueue = false;
+let presets = {};
+let lastDownloadedFile = null;
 
 
 async function checkAndDownloadBinaries() {
   try {
     const status = await BinaryManager.checkBinaries();
 
-    if (!status.yt_dlp_installed || !status.ffmpeg_installed) {
+    // ffprobe ships in the same archive as ffmpeg
+    const ffmpegMissing = !status.ffmpeg_installed || !status.ffprobe_installed;
+
+    if (!status.yt_dlp_installed || ffmpegMissing) {
       const missing = [];
       if (!status.yt_dlp_installed) missing.push('yt-dlp');
       if (!status.ffmpeg_installed) missing.push('ffmpeg');
+      if (!status.ffprobe_installed) missing.push('ffprobe');
 
       const message = `Required binaries not found: ${missing.join(', ')}\n\nDownload now? This may take a few minutes.`;
 
@@ -65,7 +76,7 @@ async function checkAndDownloadBinaries() {
             log.textContent += 'yt-dlp downloaded successfully\n';
           }
 
-          if (!status.ffmpeg_installed) {
+          if (ffmpegMissing) {
             log.textContent += 'Downloading ffmpeg...\n';
             await BinaryManager.downloadFfmpeg();
             log.textContent += 'ffmpeg downloaded successfully\n';
@@ -87,6 +98,27 @@ async function checkAndDownloadBinaries() {
   }
 }
 
+// Tell the user when the configured folder can't be used, offering to create it if missing
+async function checkDownloadDir() {
+  try {
+    const validation = await invoke('validate_download_dir');
+    if (!validation.problem) return;
+
+    if (validation.problem === 'missing') {
+      if (confirm(`${validation.message}\n\nCreate it now?`)) {
+        const created = await invoke('create_download_dir');
+        log.textContent += `${created.message}\n`;
+      }
+    } else if (validation.problem === 'low_space') {
+      log.textContent += `Warning: ${validation.message}\n`;
+    } else {
+      alert(`${validation.message}\n\nPlease choose another download folder in Settings.`);
+    }
+  } catch (error) {
+    console.error('Failed to validate download folder:', error);
+  }
+}
+
 async function loadInitialSettings() {
   try {
     const config = await invoke('get_config');
@@ -95,14 +127,23 @@ async function loadInitialSettings() {
     // Set download directory
     if (config.download_dir) {
       document.getElementById('folderInput').value = config.download_dir;
+      await checkDownloadDir();
     } else {
       const defaultDir = await invoke('get_download_dir');
       document.getElementById('folderInput').value = defaultDir;
     }
 
+    await loadPresets();
+
     // Load saved queue if remember_queue is enabled
     await loadQueueFromStorage();
 
+    // URLs the app was opened with
+    await addBackendItems(await invoke('take_launch_downloads'), true);
+    for (const request of await invoke('get_pending_links')) {
+      await confirmLink(request);
+    }
+
     console.log('Settings loaded:', config);
   } catch (error) {
     console.error('Failed to load initial settings:', error);
@@ -136,7 +177,6 @@ setupMenu();
 
 clearQueueBtn.addEventListener('click', async () => {
   queue.length = 0;  
-  localStorage.removeItem('ytdl_queue');
   editingIndex = -1;
   originalUrl = '';
   urlInput.value = '';
@@ -163,20 +203,9 @@ async function saveQueueToStorage() {
       const settings = await window.settingsManager.getCurrentSettings();
       console.log('Settings for queue save:', settings);
       
-      if (settings.remember_queue) {
-        if (queue.length > 0) {
-          const queueJson = JSON.stringify(queue);
-          localStorage.setItem('ytdl_queue', queueJson);
-          console.log('Queue saved to localStorage:', queue.length, 'items');
-        } else {
-          // Save empty queue to localStorage to clear it
-          localStorage.setItem('ytdl_queue', JSON.stringify([]));
-          console.log('Empty queue saved to localStorage');
-        }
-      } else {
-        localStorage.removeItem('ytdl_queue');
-        console.log('Queue remembering disabled, cleared localStorage');
-      }
+      // Saved by the backend so `ezdl queue` sees the same queue
+      await invoke('save_queue', { items: settings.remember_queue ? queue : [] });
+      console.log('Queue saved:', settings.remember_queue ? queue.length : 0, 'items');
     }
   } catch (error) {
     console.error('Failed to save queue:', error);
@@ -188,19 +217,26 @@ async function loadQueueFromStorage() {
     if (window.settingsManager) {
       const settings = await window.settingsManager.getCurrentSettings();
       if (settings.remember_queue) {
-        const savedQueue = localStorage.getItem('ytdl_queue');
-        if (savedQueue) {
-          const parsedQueue = JSON.parse(savedQueue);
-          if (Array.isArray(parsedQueue)) {
-            // Validate each item has required properties
-            const validItems = parsedQueue.filter(item => 
-              item && typeof item === 'object' && item.url && item.fPath
-            );
-            queue.push(...validItems);
-            updateQueueDisplay();
-            console.log('Loaded saved queue:', parsedQueue.length, 'items');
+        let savedQueue = await invoke('get_saved_queue');
+
+        // Older versions kept the queue in localStorage, move it over once
+        const legacyQueue = localStorage.getItem('ytdl_queue');
+        if (legacyQueue) {
+          const parsedQueue = JSON.parse(legacyQueue);
+          if (savedQueue.length === 0 && Array.isArray(parsedQueue)) {
+            savedQueue = parsedQueue;
+            await invoke('save_queue', { items: savedQueue });
           }
+          localStorage.removeItem('ytdl_queue');
         }
+
+        // Validate each item has required properties
+        const validItems = savedQueue.filter(item => 
+          item && typeof item === 'object' && item.url
+        );
+        queue.push(...validItems);
+        updateQueueDisplay();
+        console.log('Loaded saved queue:', savedQueue.length, 'items');
       }
     }
   } catch (error) {
@@ -208,6 +244,38 @@ async function loadQueueFromStorage() {
   }
 }
 
+// Fill the preset dropdown, keeping the current selection if it still exists
+async function loadPresets() {
+  try {
+    presets = await invoke('get_presets');
+  } catch (error) {
+    console.error('Failed to load presets:', error);
+    presets = {};
+  }
+
+  const selected = presetSelect.value;
+  presetSelect.innerHTML = '<option value="">None</option>';
+  Object.keys(presets).forEach(name => {
+    const opt = document.createElement('option');
+    opt.value = name;
+    opt.text = name;
+    presetSelect.appendChild(opt);
+  });
+  presetSelect.value = presets[selected] ? selected : '';
+}
+
+// Reflect the chosen preset in the checkboxes, which act as overrides on top of it
+presetSelect.addEventListener('change', () => {
+  const preset = presets[presetSelect.value];
+  if (!preset) return;
+  mp3OnlyCheckbox.checked = preset.audio_only;
+  enablePlayistCheckbox.checked = preset.playlist;
+  sponsorblockCheckbox.checked = preset.sponsorblock;
+  cookiesCheckbox.checked = !!preset.cookies_from_browser;
+  forceDownloadCheckbox.checked = preset.force_download;
+  if (preset.download_dir) folderPath.value = preset.download_dir;
+});
+
 // Browse for folder
 browseBtn.addEventListener('click', async () => {
   const file = await open({ multiple: false, directory: true });
@@ -236,7 +304,29 @@ addToQueueBtn.addEventListener('click', async () => {
   const enablePlaylist = enablePlayistCheckbox.checked;
   const sponsorblock = sponsorblockCheckbox.checked;
   const cookies = cookiesCheckbox.checked;
-  const item = { url, title, fPath, mp3Only, enablePlaylist, sponsorblock, cookies };
+  const forceDownload = forceDownloadCheckbox.checked;
+  const item = { url, title, fPath, preset: presetSelect.value || null, mp3Only, enablePlaylist, sponsorblock, cookies, forceDownload };
+
+  // A matching site rule supplies the folder, and the preset unless one was picked
+  try {
+    const rule = await invoke('match_site_rule', { url });
+    if (rule) {
+      if (!item.preset && presets[rule.preset]) {
+        // Checkbox state is sent as overrides, so carry the rule preset's flags into it
+        const preset = presets[rule.preset];
+        item.preset = rule.preset;
+        item.mp3Only = item.mp3Only || preset.audio_only;
+        item.enablePlaylist = item.enablePlaylist || preset.playlist;
+        item.sponsorblock = item.sponsorblock || preset.sponsorblock;
+        item.cookies = item.cookies || !!preset.cookies_from_browser;
+        item.forceDownload = item.forceDownload || preset.force_download;
+      }
+      if (rule.download_dir) item.fPath = rule.download_dir;
+      log.textContent += `Site rule "${rule.name}" applies to ${url}\n`;
+    }
+  } catch (e) {
+    console.warn('Could not match site rules:', e);
+  }
 
   if (editingIndex >= 0) {
     queue[editingIndex] = item;
@@ -339,11 +429,14 @@ function editQueueItem(index) {
     
     // Populate form with item data
     urlInput.value = item.url;
-    folderPath.value = item.fPath;
+    // Items without a folder of their own use the rule's, preset's or default one
+    folderPath.value = item.fPath || folderPath.value;
     mp3OnlyCheckbox.checked = item.mp3Only;
     enablePlayistCheckbox.checked = item.enablePlaylist;
     sponsorblockCheckbox.checked = item.sponsorblock;
     cookiesCheckbox.checked = item.cookies;
+    forceDownloadCheckbox.checked = item.forceDownload || false;
+    presetSelect.value = presets[item.preset] ? item.preset : '';
     
     addToQueueBtn.textContent = 'Update Item';
     
@@ -358,12 +451,17 @@ async function processQueue() {
     updateQueueDisplay();
 
     statusText.textContent = `Processing "${currentItem.title}"…`;
+    fileActions.hidden = true;
     statusPercent.textContent = "";
 
     try {
       await processDownload(currentItem);
     } catch (err) {
-      if (shouldStopQueue) {
+      // The backend pauses queues when disk space runs out, the current item goes back to the front
+      if (err.diskLow) shouldStopQueue = true;
+      if (!shouldStopQueue && err.updateSuggested) {
+        await retryAfterYtDlpUpdate(currentItem);
+      } else if (shouldStopQueue) {
         // Download was aborted, add item back to queue
         queue.unshift(currentItem);
         updateQueueDisplay();
@@ -389,19 +487,62 @@ async function processQueue() {
   downloadBtn.textContent = 'Process Queue';
 }
 
+// Offer a yt-dlp update after an extractor failure and retry the item once
+async function retryAfterYtDlpUpdate(item) {
+  if (!confirm(`yt-dlp could not extract "${item.title || item.url}". This is usually fixed by updating yt-dlp.\n\nUpdate now and retry?`)) {
+    return;
+  }
+
+  try {
+    await BinaryManager.downloadYtDlp();
+    log.textContent += 'yt-dlp updated, retrying download...\n';
+    log.scrollTop = log.scrollHeight;
+    await processDownload(item);
+  } catch (error) {
+    log.textContent += `Retry failed: ${error}\n`;
+    log.scrollTop = log.scrollHeight;
+  }
+}
+
+openFileBtn.addEventListener('click', async () => {
+  try {
+    await invoke('open_download', { path: lastDownloadedFile });
+  } catch (error) {
+    alert(error);
+  }
+});
+
+showInFolderBtn.addEventListener('click', async () => {
+  try {
+    await invoke('show_download_in_folder', { path: lastDownloadedFile });
+  } catch (error) {
+    alert(error);
+  }
+});
+
 // Trigger backend download
 async function processDownload(item) {
   try {
+    // Keep the preset's browser when cookies are on, clear it when off
+    const presetBrowser = presets[item.preset]?.cookies_from_browser;
     await invoke('download_url', {
       url: item.url,
       fPath: item.fPath,
-      mp3Only: item.mp3Only || false,
-      enablePlaylist: item.enablePlaylist || false,
-      sponsorblock: item.sponsorblock || false,
-      cookies: item.cookies || false
+      preset: item.preset || null,
+      overrides: {
+        audio_only: item.mp3Only || false,
+        playlist: item.enablePlaylist || false,
+        sponsorblock: item.sponsorblock || false,
+        force_download: item.forceDownload || false,
+        cookies_from_browser: item.cookies ? (presetBrowser || 'firefox') : ''
+      }
     });
   } catch (error) {
-    throw new Error(`Download failed for ${item.url}: ${error}`);
+    // download_url fails with { message, code, update_suggested, disk_low }
+    const failure = new Error(`Download failed for ${item.url}: ${error?.message ?? error}`);
+    failure.updateSuggested = !!error?.update_suggested;
+    failure.diskLow = !!error?.disk_low;
+    throw failure;
   }
 }
 
@@ -439,8 +580,13 @@ await listen('download-complete', event => {
   statusText.textContent = `✅ Completed "${rawTitle}"`;
   statusPercent.textContent = "";
 
-  log.textContent += `Download completed with code ${event.payload}\n`;
+  const { code, files } = event.payload;
+  log.textContent += `Download completed with code ${code}\n`;
+  files.forEach(file => log.textContent += `Saved ${file}\n`);
   log.scrollTop = log.scrollHeight;
+
+  lastDownloadedFile = files.length > 0 ? files[files.length - 1] : null;
+  fileActions.hidden = !lastDownloadedFile;
 });
 
 await listen('download-error', event => {
@@ -452,6 +598,121 @@ await listen('download-error', event => {
   log.scrollTop = log.scrollHeight;
 });
 
+await listen('config-changed', event => {
+  if (event.payload.keys.some(key => key.startsWith('presets'))) {
+    loadPresets();
+  }
+});
+
+await listen('config-warning', event => {
+  log.textContent += `Settings: ${event.payload}\n`;
+  log.scrollTop = log.scrollHeight;
+});
+
+// Downloads added by the backend, e.g. new videos from subscriptions
+async function addBackendItems(items, start) {
+  let added = 0;
+
+  for (const queued of items) {
+    if (queue.some(item => item.url === queued.url)) continue;
+
+    const preset = presets[queued.preset];
+    queue.push({
+      url: queued.url,
+      title: queued.title || queued.url,
+      // Left unset so site rule and preset folders still apply when it downloads
+      fPath: queued.download_dir || null,
+      preset: preset ? queued.preset : null,
+      mp3Only: preset?.audio_only || false,
+      enablePlaylist: preset?.playlist || false,
+      sponsorblock: preset?.sponsorblock || false,
+      cookies: !!preset?.cookies_from_browser,
+      forceDownload: preset?.force_download || false
+    });
+    log.textContent += `Added to queue from ${queued.source}: ${queued.url}\n`;
+    added++;
+  }
+  if (added === 0) return;
+
+  log.scrollTop = log.scrollHeight;
+  updateQueueDisplay();
+  await saveQueueToStorage();
+
+  if (start && !processing) {
+    shouldStopQueue = false;
+    downloadBtn.textContent = 'Stop Queue';
+    processQueue();
+  }
+}
+
+await listen('queue-add', event => addBackendItems(event.payload.items, event.payload.start));
+
+// ezdl:// links only download once the user agrees
+const askedLinks = new Set();
+async function confirmLink(request) {
+  if (askedLinks.has(request.id)) return;
+  askedLinks.add(request.id);
+
+  const preset = request.preset ? ` with preset "${request.preset}"` : '';
+  const accept = confirm(`A link asks to download:\n\n${request.url}${preset}\n\nDownload it?`);
+  try {
+    await invoke('resolve_link', { id: request.id, accept });
+  } catch (error) {
+    console.error('Failed to answer link:', error);
+  }
+}
+
+await listen('deep-link-confirm', event => confirmLink(event.payload));
+
+await listen('deep-link-rejected', event => {
+  const { link, message } = event.payload;
+  log.textContent += `Ignored link ${link}: ${message}\n`;
+  log.scrollTop = log.scrollHeight;
+});
+
+// Links from a text, CSV or bookmarks file; new ones arrive as queue-add
+document.addEventListener('importUrlsRequested', async event => {
+  try {
+    const report = await invoke('import_urls', {
+      path: event.detail.path,
+      queued: queue.map(item => item.url)
+    });
+
+    for (const invalid of report.invalid) {
+      log.textContent += `Line ${invalid.line}: ${invalid.reason}${invalid.text ? ` (${invalid.text})` : ''}\n`;
+    }
+    log.scrollTop = log.scrollHeight;
+
+    const skipped = [];
+    if (report.duplicates) skipped.push(`${report.duplicates} already queued or repeated`);
+    if (report.already_downloaded) skipped.push(`${report.already_downloaded} already downloaded`);
+    if (report.invalid.length) skipped.push(`${report.invalid.length} invalid, see the log`);
+    const notes = skipped.length ? `\n\nSkipped: ${skipped.join(', ')}.` : '';
+    alert(`Added ${report.items.length} links to the queue.${notes}`);
+  } catch (error) {
+    alert(`Failed to import URLs: ${error}`);
+  }
+});
+
+// Video links copied elsewhere, suggested in the URL box
+await listen('clipboard-url-detected', event => {
+  const { url } = event.payload;
+  if (queue.some(item => item.url === url)) return;
+
+  log.textContent += `Copied link detected: ${url}\n`;
+  log.scrollTop = log.scrollHeight;
+  if (!urlInput.value.trim() && editingIndex < 0) {
+    urlInput.value = url;
+    urlInput.focus();
+  }
+});
+
+await listen('disk-low', event => {
+  log.textContent += `${event.payload.message}\n`;
+  log.scrollTop = log.scrollHeight;
+  alert(`${event.payload.message}\n\nFree up some space, then process the queue again.`);
+});
+
 await listen('download-log', event => {
   console.log('[download-log]', event.payload)
   log.textContent += event.payload + '\n'
@@ -2,6 +2,9 @@ import { Menu, MenuItem, Submenu } from "@tauri-apps/api/menu";
 import { exit } from "@tauri-apps/plugin-process";
 import { openUrl } from '@tauri-apps/plugin-opener';
 import { invoke } from '@tauri-apps/api/core';
+import { open, save } from '@tauri-apps/plugin-dialog';
+
+const bundleFilters = [{ name: 'Settings', extensions: ['json', 'toml'] }];
 
 export async function setupMenu() {
   const fileSubmenu = await Submenu.new({
@@ -50,6 +53,49 @@ export async function setupMenu() {
           }
         },
       }),
+      await MenuItem.new({
+        id: "import_urls",
+        text: "Import URLs...",
+        action: async () => {
+          const path = await open({
+            multiple: false,
+            filters: [{ name: 'Link lists', extensions: ['txt', 'csv', 'tsv', 'html', 'htm'] }]
+          });
+          if (!path) return;
+          // The main window knows the queue, let it run the import
+          document.dispatchEvent(new CustomEvent('importUrlsRequested', { detail: { path } }));
+        },
+      }),
+      await MenuItem.new({
+        id: "export_settings",
+        text: "Export Settings...",
+        action: async () => {
+          const path = await save({ defaultPath: 'ezdl-settings.json', filters: bundleFilters });
+          if (!path) return;
+          const includeMachinePaths = confirm("Include download folders, output templates and binary paths?\n\nChoose Cancel to share settings with other machines.");
+          try {
+            await invoke('export_config', { path, includeMachinePaths });
+            alert('Settings exported.');
+          } catch (error) {
+            alert(`Failed to export settings: ${error}`);
+          }
+        },
+      }),
+      await MenuItem.new({
+        id: "import_settings",
+        text: "Import Settings...",
+        action: async () => {
+          const path = await open({ multiple: false, filters: bundleFilters });
+          if (!path) return;
+          try {
+            const summary = await invoke('import_config', { path, includeMachinePaths: false });
+            const notes = summary.warnings.length ? `\n\n${summary.warnings.join('\n')}` : '';
+            alert(`Imported settings with ${summary.presets} presets and ${summary.site_rules} site rules.${notes}`);
+          } catch (error) {
+            alert(`Failed to import settings: ${error}`);
+          }
+        },
+      }),
       await MenuItem.new({
         id: "quit",
         text: "Quit",
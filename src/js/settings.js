@@ -15,6 +15,9 @@ class SettingsManager {
       this.fontSizeInput = document.getElementById('fontSizeInput');
       this.rememberQueueCheckbox = document.getElementById('rememberQueueCheckbox');
       this.useSystemBinariesCheckbox = document.getElementById('useSystemBinariesCheckbox');
+      this.clipboardModeSelect = document.getElementById('clipboardModeSelect');
+      this.clipboardPresetSelect = document.getElementById('clipboardPresetSelect');
+      this.clipboardAnySiteCheckbox = document.getElementById('clipboardAnySiteCheckbox');
 
       this.setupEventListeners();
 
@@ -41,6 +44,9 @@ class SettingsManager {
     this.downloadBinariesBtn.onclick = () => this.downloadBinaries();
 
     this.fontSizeInput.oninput = () => this.updateFontSizePreview();
+
+    // The preset only applies to links added to the queue
+    this.clipboardModeSelect.onchange = () => this.updateClipboardPreset();
   }
 
   async loadCurrentSettings() {
@@ -52,7 +58,19 @@ class SettingsManager {
       this.rememberQueueCheckbox.checked = config.remember_queue || true;
       this.useSystemBinariesCheckbox.checked = config.use_system_binaries !== false;
 
+      this.clipboardPresetSelect.innerHTML = '<option value="">No preset</option>';
+      Object.keys(config.presets || {}).forEach(name => {
+        const opt = document.createElement('option');
+        opt.value = name;
+        opt.textContent = name;
+        this.clipboardPresetSelect.appendChild(opt);
+      });
+      this.clipboardModeSelect.value = config.clipboard?.mode || 'off';
+      this.clipboardPresetSelect.value = config.clipboard?.preset || '';
+      this.clipboardAnySiteCheckbox.checked = !!config.clipboard?.any_site;
+
       this.updateFontSizePreview();
+      this.updateClipboardPreset();
     } catch (error) {
       console.error('Failed to load settings:', error);
       // Fallback to defaults
@@ -66,14 +84,20 @@ class SettingsManager {
 
   async saveSettings() {
     try {
-      const config = {
+      // Patch only the fields this pane owns so other settings are kept
+      const patch = {
         download_dir: this.downloadDirInput.value || null,
         font_size: Math.max(8, Math.min(20, parseInt(this.fontSizeInput.value) || 14)),
         remember_queue: this.rememberQueueCheckbox.checked,
-        use_system_binaries: this.useSystemBinariesCheckbox.checked
+        use_system_binaries: this.useSystemBinariesCheckbox.checked,
+        clipboard: {
+          mode: this.clipboardModeSelect.value,
+          preset: this.clipboardPresetSelect.value || null,
+          any_site: this.clipboardAnySiteCheckbox.checked
+        }
       };
 
-      await invoke('update_config', { newConfig: config });
+      const config = await invoke('patch_config', { patch });
       
       // Apply font size globally immediately
       document.documentElement.style.setProperty('--font-size', `${config.font_size}px`);
@@ -159,6 +183,10 @@ class SettingsManager {
     }
   }
 
+  updateClipboardPreset() {
+    this.clipboardPresetSelect.style.display = this.clipboardModeSelect.value === 'enqueue' ? '' : 'none';
+  }
+
   updateFontSizePreview() {
     const fontSize = this.fontSizeInput.value;
     const preview = document.querySelector('.font-size-preview');